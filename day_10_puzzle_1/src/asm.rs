@@ -0,0 +1,100 @@
+use crate::cpu::Instruction;
+use std::collections::HashMap;
+
+fn strip_comment(line: &str) -> &str {
+    match line.find(['#', ';']) {
+        Some(idx) => &line[..idx],
+        None => line,
+    }
+}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Assembles a program. Comments start with # or ; and run to the end of the
+// line, `name:` labels the next instruction and may share its line. Jumps
+// take a label or an instruction index. A plain puzzle input assembles to
+// the same program as `read_program` gives.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, String> {
+    // first pass, the labels and the lines with instructions
+    let mut labels = HashMap::new();
+    let mut lines = vec![];
+    for (idx, line) in source.lines().enumerate() {
+        let mut code = strip_comment(line).trim();
+        while let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(format!("Line {}: Bad label {:?}!", idx + 1, label));
+            }
+            if labels.insert(label, lines.len()).is_some() {
+                return Err(format!("Line {}: Label {} defined twice!", idx + 1, label));
+            }
+            code = rest.trim();
+        }
+        if !code.is_empty() {
+            lines.push((idx + 1, code));
+        }
+    }
+
+    let target = |operand: &str| match labels.get(operand) {
+        Some(&target) => Ok(target),
+        None => operand
+            .parse()
+            .map_err(|_| format!("Unknown label {}!", operand)),
+    };
+    lines
+        .iter()
+        .map(|&(line, code)| {
+            Instruction::parse_with(code, target).map_err(|err| format!("Line {}: {}", line, err))
+        })
+        .collect()
+}
+
+// Pretty-prints a program with a label at every jump target and the index
+// of each instruction in a comment. Assembling it gives back the program.
+pub fn disassemble(program: &[Instruction]) -> String {
+    // targets further past the end stay numbers
+    let mut targets = program
+        .iter()
+        .filter_map(Instruction::target)
+        .filter(|&target| target <= program.len())
+        .collect::<Vec<_>>();
+    targets.sort_unstable();
+    targets.dedup();
+    let labels = targets
+        .iter()
+        .enumerate()
+        .map(|(n, &target)| (target, format!("label_{}", n)))
+        .collect::<HashMap<_, _>>();
+
+    let mut output = String::new();
+    for (idx, instruction) in program.iter().enumerate() {
+        if let Some(label) = labels.get(&idx) {
+            output.push_str(&format!("{}:\n", label));
+        }
+        let target = instruction
+            .target()
+            .map(|target| {
+                labels
+                    .get(&target)
+                    .cloned()
+                    .unwrap_or_else(|| target.to_string())
+            })
+            .unwrap_or_default();
+        output.push_str(&format!(
+            "    {:<20} ; {}\n",
+            instruction.fmt_with_target(&target),
+            idx
+        ));
+    }
+    // jumping right past the end finishes the program
+    if let Some(label) = labels.get(&program.len()) {
+        output.push_str(&format!("{}:\n", label));
+    }
+    output
+}
@@ -0,0 +1,459 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+pub type Register = i64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reg {
+    X,
+    Y,
+}
+
+impl Reg {
+    pub const ALL: [Reg; 2] = [Reg::X, Reg::Y];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Reg::X => write!(f, "x"),
+            Reg::Y => write!(f, "y"),
+        }
+    }
+}
+
+impl FromStr for Reg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" | "X" => Ok(Reg::X),
+            "y" | "Y" => Ok(Reg::Y),
+            _ => Err(format!("Unknown register {}!", s)),
+        }
+    }
+}
+
+// Jump targets are indices into the program, jumping past its end finishes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Addx { value: Register },
+    Addy { value: Register },
+    Mulx { value: Register },
+    Jmp { target: usize },
+    // jumps if the register is zero
+    Jz { register: Reg, target: usize },
+    // jumps if the register is not zero
+    Jnz { register: Reg, target: usize },
+    Halt,
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Instruction::Noop => Opcode::Noop,
+            Instruction::Addx { .. } => Opcode::Addx,
+            Instruction::Addy { .. } => Opcode::Addy,
+            Instruction::Mulx { .. } => Opcode::Mulx,
+            Instruction::Jmp { .. } => Opcode::Jmp,
+            Instruction::Jz { .. } => Opcode::Jz,
+            Instruction::Jnz { .. } => Opcode::Jnz,
+            Instruction::Halt => Opcode::Halt,
+        }
+    }
+
+    pub fn target(&self) -> Option<usize> {
+        match self {
+            Instruction::Jmp { target }
+            | Instruction::Jz { target, .. }
+            | Instruction::Jnz { target, .. } => Some(*target),
+            _ => None,
+        }
+    }
+
+    // Parses an instruction, `target` turns the jump operands into program
+    // indices so the assembler can resolve labels.
+    pub fn parse_with(
+        s: &str,
+        target: impl Fn(&str) -> Result<usize, String>,
+    ) -> Result<Self, String> {
+        let mut split = s.split_whitespace();
+        let instruction = split
+            .next()
+            .ok_or_else(|| "Empty instruction!".to_owned())?;
+        let mut operand = || {
+            split
+                .next()
+                .ok_or_else(|| format!("{} expects more arguments!", instruction))
+        };
+        let value = |operand: &str| {
+            operand
+                .parse::<Register>()
+                .map_err(|err| format!("Bad value {}: {}", operand, err))
+        };
+        let opcode = Opcode::from_str(instruction)?;
+        let result = match opcode {
+            Opcode::Noop => Instruction::Noop,
+            Opcode::Addx => Instruction::Addx {
+                value: value(operand()?)?,
+            },
+            Opcode::Addy => Instruction::Addy {
+                value: value(operand()?)?,
+            },
+            Opcode::Mulx => Instruction::Mulx {
+                value: value(operand()?)?,
+            },
+            Opcode::Jmp => Instruction::Jmp {
+                target: target(operand()?)?,
+            },
+            Opcode::Jz => Instruction::Jz {
+                register: operand()?.parse()?,
+                target: target(operand()?)?,
+            },
+            Opcode::Jnz => Instruction::Jnz {
+                register: operand()?.parse()?,
+                target: target(operand()?)?,
+            },
+            Opcode::Halt => Instruction::Halt,
+        };
+        if split.next().is_some() {
+            return Err(format!("Too many arguments: {}", s));
+        }
+        Ok(result)
+    }
+
+    // Writes the instruction with the given text in place of the jump target.
+    pub fn fmt_with_target(&self, target: &str) -> String {
+        let opcode = self.opcode();
+        match self {
+            Instruction::Noop | Instruction::Halt => opcode.to_string(),
+            Instruction::Addx { value }
+            | Instruction::Addy { value }
+            | Instruction::Mulx { value } => {
+                format!("{} {}", opcode, value)
+            }
+            Instruction::Jmp { .. } => format!("{} {}", opcode, target),
+            Instruction::Jz { register, .. } | Instruction::Jnz { register, .. } => {
+                format!("{} {} {}", opcode, register, target)
+            }
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let target = self.target().map(|t| t.to_string()).unwrap_or_default();
+        write!(f, "{}", self.fmt_with_target(&target))
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, |target| {
+            target
+                .parse()
+                .map_err(|_| format!("Bad jump target {}!", target))
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opcode {
+    Noop,
+    Addx,
+    Addy,
+    Mulx,
+    Jmp,
+    Jz,
+    Jnz,
+    Halt,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 8] = [
+        Opcode::Noop,
+        Opcode::Addx,
+        Opcode::Addy,
+        Opcode::Mulx,
+        Opcode::Jmp,
+        Opcode::Jz,
+        Opcode::Jnz,
+        Opcode::Halt,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Opcode::Noop => "noop",
+            Opcode::Addx => "addx",
+            Opcode::Addy => "addy",
+            Opcode::Mulx => "mulx",
+            Opcode::Jmp => "jmp",
+            Opcode::Jz => "jz",
+            Opcode::Jnz => "jnz",
+            Opcode::Halt => "halt",
+        }
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Opcode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Opcode::ALL
+            .into_iter()
+            .find(|opcode| opcode.name() == s)
+            .ok_or_else(|| format!("Unknown instruction {}!", s))
+    }
+}
+
+// How many cycles each instruction takes, noop and the jumps take one
+// cycle and the arithmetic two by default, like addx in the puzzle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CycleCosts([u32; Opcode::ALL.len()]);
+
+impl CycleCosts {
+    pub fn get(&self, opcode: Opcode) -> u32 {
+        self.0[opcode as usize]
+    }
+
+    pub fn set(&mut self, opcode: Opcode, cycles: u32) -> Result<(), String> {
+        if cycles == 0 {
+            return Err(format!("{} has to take at least one cycle!", opcode));
+        }
+        self.0[opcode as usize] = cycles;
+        Ok(())
+    }
+}
+
+impl Default for CycleCosts {
+    fn default() -> Self {
+        let mut costs = [1; Opcode::ALL.len()];
+        for opcode in [Opcode::Addx, Opcode::Addy, Opcode::Mulx] {
+            costs[opcode as usize] = 2;
+        }
+        Self(costs)
+    }
+}
+
+// Overrides of the default costs, e.g. "mulx=4,jnz=2".
+impl FromStr for CycleCosts {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut costs = Self::default();
+        for cost in s.split(',').filter(|cost| !cost.trim().is_empty()) {
+            let (opcode, cycles) = cost
+                .split_once('=')
+                .ok_or_else(|| format!("Expected <instruction>=<cycles>, got {}", cost))?;
+            let cycles = cycles
+                .trim()
+                .parse()
+                .map_err(|_| format!("Bad number of cycles: {}", cycles))?;
+            costs.set(opcode.trim().parse()?, cycles)?;
+        }
+        Ok(costs)
+    }
+}
+
+// Parses a program skipping empty lines, errors point at the offending line.
+pub fn read_program<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<Instruction>, String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            Instruction::from_str(line.trim()).map_err(|err| format!("Line {}: {}", idx + 1, err))
+        })
+        .collect()
+}
+
+// Gets notified about every clock cycle of the CPU. During a cycle the
+// registers still hold the values from before the current instruction
+// finishes, after the cycle they hold its result.
+pub trait Observer {
+    fn during(&mut self, _cycle: u64, _cpu: &Cpu) {}
+
+    fn after(&mut self, _cycle: u64, _cpu: &Cpu) {}
+
+    // Whether the observer has seen all the cycles it cares about, a program
+    // which never halts runs until then.
+    fn is_done(&self) -> bool {
+        false
+    }
+}
+
+// Does nothing, for running the CPU just for its registers.
+impl Observer for () {}
+
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn during(&mut self, cycle: u64, cpu: &Cpu) {
+        self.0.during(cycle, cpu);
+        self.1.during(cycle, cpu);
+    }
+
+    fn after(&mut self, cycle: u64, cpu: &Cpu) {
+        self.0.after(cycle, cpu);
+        self.1.after(cycle, cpu);
+    }
+
+    fn is_done(&self) -> bool {
+        self.0.is_done() && self.1.is_done()
+    }
+}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn during(&mut self, cycle: u64, cpu: &Cpu) {
+        (**self).during(cycle, cpu);
+    }
+
+    fn after(&mut self, cycle: u64, cpu: &Cpu) {
+        (**self).after(cycle, cpu);
+    }
+
+    fn is_done(&self) -> bool {
+        (**self).is_done()
+    }
+}
+
+pub struct Cpu {
+    program: Vec<Instruction>,
+    costs: CycleCosts,
+    registers: [Register; Reg::ALL.len()],
+    halted: bool,
+    // index of the instruction being executed
+    pc: usize,
+    // cycles the current instruction has been running for
+    elapsed: u32,
+    cycle: u64,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self::with_costs(program, CycleCosts::default())
+    }
+
+    // X starts at 1 like in the puzzle, the other registers at 0.
+    pub fn with_costs(program: Vec<Instruction>, costs: CycleCosts) -> Self {
+        Self {
+            program,
+            costs,
+            registers: [1, 0],
+            halted: false,
+            pc: 0,
+            elapsed: 0,
+            cycle: 0,
+        }
+    }
+
+    pub fn x(&self) -> Register {
+        self.register(Reg::X)
+    }
+
+    pub fn register(&self, register: Reg) -> Register {
+        self.registers[register.index()]
+    }
+
+    pub fn costs(&self) -> &CycleCosts {
+        &self.costs
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    // Cycles the current instruction has been running for, 0 when it's
+    // about to start.
+    pub fn elapsed(&self) -> u32 {
+        self.elapsed
+    }
+
+    // Number of cycles completed so far.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    // The instruction being executed, None once the program is done.
+    pub fn current(&self) -> Option<&Instruction> {
+        match self.halted {
+            true => None,
+            false => self.program.get(self.pc),
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.current().is_none()
+    }
+
+    // Runs a single cycle, returns false if the program was already done.
+    pub fn tick(&mut self, observer: &mut impl Observer) -> bool {
+        let Some(&instruction) = self.current() else {
+            return false;
+        };
+        self.cycle += 1;
+        observer.during(self.cycle, self);
+        self.elapsed += 1;
+        if self.elapsed == self.costs.get(instruction.opcode()) {
+            self.elapsed = 0;
+            self.execute(instruction);
+        }
+        observer.after(self.cycle, self);
+        true
+    }
+
+    // Runs until the program is done or the observer doesn't need any more
+    // cycles.
+    pub fn run(&mut self, observer: &mut impl Observer) {
+        while !observer.is_done() && self.tick(observer) {}
+    }
+
+    fn execute(&mut self, instruction: Instruction) {
+        let mut next = self.pc + 1;
+        match instruction {
+            Instruction::Noop => {}
+            Instruction::Addx { value } => self.apply(Reg::X, |x| x.wrapping_add(value)),
+            Instruction::Addy { value } => self.apply(Reg::Y, |y| y.wrapping_add(value)),
+            Instruction::Mulx { value } => self.apply(Reg::X, |x| x.wrapping_mul(value)),
+            Instruction::Jmp { target } => next = target,
+            Instruction::Jz { register, target } => {
+                if self.register(register) == 0 {
+                    next = target;
+                }
+            }
+            Instruction::Jnz { register, target } => {
+                if self.register(register) != 0 {
+                    next = target;
+                }
+            }
+            Instruction::Halt => {
+                // stays on the halt for anyone inspecting the CPU
+                self.halted = true;
+                next = self.pc;
+            }
+        }
+        self.pc = next;
+    }
+
+    fn apply(&mut self, register: Reg, operation: impl Fn(Register) -> Register) {
+        let value = &mut self.registers[register.index()];
+        *value = operation(*value);
+    }
+}
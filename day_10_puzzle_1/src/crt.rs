@@ -0,0 +1,199 @@
+use crate::cpu::{Cpu, Observer, Register};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+pub const WIDTH: usize = 40;
+pub const HEIGHT: usize = 6;
+pub const SPRITE_WIDTH: usize = 3;
+
+// What happens to the part of the sprite that sticks out of the row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Wrap {
+    // it's cut off, like in the puzzle
+    #[default]
+    Clip,
+    // it comes back on the other side of the same row
+    Around,
+}
+
+impl FromStr for Wrap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clip" => Ok(Wrap::Clip),
+            "around" => Ok(Wrap::Around),
+            _ => Err(format!("Unknown wrap mode {}!", s)),
+        }
+    }
+}
+
+// Size of the screen and the sprite. The beam draws the rows left to right
+// one pixel per cycle and stops after the last row. X is the column of the
+// sprite within the row being drawn, an even wide sprite has its extra pixel
+// on the right of X.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Geometry {
+    pub width: usize,
+    pub height: usize,
+    pub sprite_width: usize,
+    pub wrap: Wrap,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self {
+            width: WIDTH,
+            height: HEIGHT,
+            sprite_width: SPRITE_WIDTH,
+            wrap: Wrap::Clip,
+        }
+    }
+}
+
+// Draws a pixel during every cycle, lit if the sprite at X covers the column
+// being drawn.
+pub struct Crt {
+    geometry: Geometry,
+    pixels: Vec<bool>,
+    // pixels the beam went over so far
+    drawn: usize,
+}
+
+impl Crt {
+    pub fn new() -> Self {
+        Self::with_geometry(Geometry::default()).unwrap()
+    }
+
+    pub fn with_geometry(geometry: Geometry) -> Result<Self, String> {
+        if geometry.width == 0 || geometry.height == 0 || geometry.sprite_width == 0 {
+            return Err(format!(
+                "The screen and the sprite can't be empty, got {}x{} with a sprite of {}!",
+                geometry.width, geometry.height, geometry.sprite_width
+            ));
+        }
+        Ok(Self {
+            geometry,
+            pixels: vec![false; geometry.width * geometry.height],
+            drawn: 0,
+        })
+    }
+
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+
+    pub fn width(&self) -> usize {
+        self.geometry.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.geometry.height
+    }
+
+    // Row and column of the pixel drawn during the cycle, None once the beam
+    // went past the last pixel.
+    pub fn position(&self, cycle: u64) -> Option<(usize, usize)> {
+        let position = (cycle - 1) as usize;
+        (position < self.pixels.len()).then_some((position / self.width(), position % self.width()))
+    }
+
+    // Whether the sprite at X covers the column.
+    pub fn sprite_covers(&self, x: Register, col: usize) -> bool {
+        let Geometry {
+            width,
+            sprite_width,
+            wrap,
+            ..
+        } = self.geometry;
+        // X wraps around in the CPU, so it can be anywhere near the limits
+        let start = x as i128 - (sprite_width as i128 - 1) / 2;
+        let offset = col as i128 - start;
+        match wrap {
+            Wrap::Clip => (0..sprite_width as i128).contains(&offset),
+            Wrap::Around => {
+                sprite_width >= width || offset.rem_euclid(width as i128) < sprite_width as i128
+            }
+        }
+    }
+
+    pub fn is_lit(&self, row: usize, col: usize) -> bool {
+        self.pixels[row * self.width() + col]
+    }
+
+    // The screen as lines of # and .
+    pub fn render(&self) -> String {
+        self.pixels
+            .chunks(self.width())
+            .map(|row| {
+                row.iter()
+                    .map(|&lit| if lit { '#' } else { '.' })
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    // The screen for a colour terminal, every character shows two rows of
+    // pixels as an upper half block coloured by the top pixel and put on the
+    // background of the bottom one.
+    pub fn render_ansi(&self) -> String {
+        let colour = |lit: bool| if lit { 93 } else { 90 };
+        let mut output = String::new();
+        for row in (0..self.height()).step_by(2) {
+            for col in 0..self.width() {
+                let top = self.is_lit(row, col);
+                let bottom = row + 1 < self.height() && self.is_lit(row + 1, col);
+                let background = match row + 1 < self.height() {
+                    true => colour(bottom) + 10,
+                    // no row below, the terminal's own background
+                    false => 49,
+                };
+                output.push_str(&format!("\x1b[{};{}m\u{2580}", colour(top), background));
+            }
+            output.push_str("\x1b[0m\n");
+        }
+        output
+    }
+
+    // Plain PBM with lit pixels in black, each pixel as a `scale`x`scale` block.
+    pub fn write_pbm<W: Write>(&self, mut writer: W, scale: usize) -> io::Result<()> {
+        writeln!(
+            writer,
+            "P1\n{} {}",
+            self.width() * scale,
+            self.height() * scale
+        )?;
+        for row in self.pixels.chunks(self.width()) {
+            let line = row
+                .iter()
+                .flat_map(|&lit| std::iter::repeat_n(if lit { "1" } else { "0" }, scale))
+                .collect::<Vec<_>>()
+                .join(" ");
+            for _ in 0..scale {
+                writeln!(writer, "{}", line)?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Observer for Crt {
+    fn during(&mut self, cycle: u64, cpu: &Cpu) {
+        if let Some((row, col)) = self.position(cycle) {
+            let idx = row * self.width() + col;
+            self.pixels[idx] = self.sprite_covers(cpu.x(), col);
+            self.drawn = idx + 1;
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.drawn == self.pixels.len()
+    }
+}
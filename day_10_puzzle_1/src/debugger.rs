@@ -0,0 +1,460 @@
+use crate::cpu::{Cpu, Observer, Reg, Register};
+use crate::crt::Crt;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    // longer operators first so that <= isn't read as <
+    const ALL: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Eq),
+        ("!=", Comparison::Ne),
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    pub fn holds(self, lhs: Register, rhs: Register) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        Self::ALL.iter().find(|(_, c)| *c == self).unwrap().0
+    }
+}
+
+// Breakpoints are checked before every cycle. A register condition only
+// stops the program when it becomes true, not on every cycle it holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    // before the given cycle starts
+    Cycle(u64),
+    // before the instruction at the given index starts
+    Instruction(usize),
+    Register {
+        register: Reg,
+        comparison: Comparison,
+        value: Register,
+    },
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle={}", cycle),
+            Breakpoint::Instruction(idx) => write!(f, "instr={}", idx),
+            Breakpoint::Register {
+                register,
+                comparison,
+                value,
+            } => write!(f, "{}{}{}", register, comparison.symbol(), value),
+        }
+    }
+}
+
+// "cycle=<n>", "instr=<n>" or a register condition like "x>=10" or "y==0".
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.replace(' ', "");
+        let number = |n: &str| {
+            n.parse()
+                .map_err(|_| format!("Bad breakpoint {}, {} is not a number!", s, n))
+        };
+        if let Some(cycle) = s.strip_prefix("cycle=") {
+            return Ok(Breakpoint::Cycle(number(cycle)?));
+        }
+        if let Some(idx) = s.strip_prefix("instr=") {
+            return Ok(Breakpoint::Instruction(number(idx)? as usize));
+        }
+        let (symbol, comparison) = Comparison::ALL
+            .iter()
+            .find(|(symbol, _)| s.contains(symbol))
+            .ok_or_else(|| format!("Bad breakpoint {}!", s))?;
+        let (register, value) = s.split_once(symbol).unwrap();
+        Ok(Breakpoint::Register {
+            register: register.parse()?,
+            comparison: *comparison,
+            value: value
+                .parse()
+                .map_err(|_| format!("Bad breakpoint {}, {} is not a number!", s, value))?,
+        })
+    }
+}
+
+// A row of the trace, the state during a cycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceRow {
+    pub cycle: u64,
+    pub x: Register,
+    // position of the pixel the beam draws, None past the end of the screen
+    pub pixel: Option<usize>,
+    pub lit: bool,
+}
+
+// Rows kept by default, a program that never halts shouldn't eat all the memory.
+pub const TRACE_LIMIT: usize = 100_000;
+
+// Records the cycles as the CRT sees them, up to the limit.
+pub struct Trace {
+    rows: Vec<TraceRow>,
+    limit: usize,
+    // cycles past the limit which weren't recorded
+    dropped: u64,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self {
+            rows: vec![],
+            limit: TRACE_LIMIT,
+            dropped: 0,
+        }
+    }
+}
+
+impl Trace {
+    pub fn rows(&self) -> &[TraceRow] {
+        &self.rows
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    // Needs the CRT to know the pixels, so the debugger feeds it.
+    fn record(&mut self, cycle: u64, cpu: &Cpu, crt: &Crt) {
+        if self.rows.len() >= self.limit {
+            self.dropped += 1;
+            return;
+        }
+        let position = crt.position(cycle);
+        self.rows.push(TraceRow {
+            cycle,
+            x: cpu.x(),
+            pixel: position.map(|(row, col)| row * crt.width() + col),
+            lit: position.is_some_and(|(_, col)| crt.sprite_covers(cpu.x(), col)),
+        });
+    }
+
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "cycle,x,pixel,lit")?;
+        for row in &self.rows {
+            let pixel = row.pixel.map(|p| p.to_string()).unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{}",
+                row.cycle, row.x, pixel, row.lit as u8
+            )?;
+        }
+        writer.flush()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    // index of the breakpoint that was hit
+    Breakpoint(usize),
+    Halted,
+    Stepped,
+    // the last pixel of the screen was drawn
+    ScreenDone,
+    // ran the whole cycle budget without hitting anything
+    Budget,
+}
+
+// Cycles a `continue` runs at most by default.
+pub const BUDGET: u64 = 1_000_000;
+
+// Runs a program on the CPU wired to a CRT, stopping at breakpoints.
+pub struct Debugger {
+    cpu: Cpu,
+    crt: Crt,
+    trace: Trace,
+    breakpoints: Vec<Breakpoint>,
+    // last state of the register conditions
+    held: Vec<bool>,
+    // cycle at which the debugger last stopped on a breakpoint
+    stopped_at: Option<u64>,
+    budget: u64,
+}
+
+struct Observers<'a> {
+    crt: &'a mut Crt,
+    trace: &'a mut Trace,
+}
+
+impl Observer for Observers<'_> {
+    fn during(&mut self, cycle: u64, cpu: &Cpu) {
+        self.trace.record(cycle, cpu, self.crt);
+        self.crt.during(cycle, cpu);
+    }
+
+    fn is_done(&self) -> bool {
+        self.crt.is_done()
+    }
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Self::with_crt(cpu, Crt::new())
+    }
+
+    pub fn with_crt(cpu: Cpu, crt: Crt) -> Self {
+        Self {
+            cpu,
+            crt,
+            trace: Trace::default(),
+            breakpoints: vec![],
+            held: vec![],
+            stopped_at: None,
+            budget: BUDGET,
+        }
+    }
+
+    // Most cycles a single `resume` runs.
+    pub fn set_budget(&mut self, cycles: u64) {
+        self.budget = cycles;
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn crt(&self) -> &Crt {
+        &self.crt
+    }
+
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.held.push(self.holds(&breakpoint));
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, idx: usize) -> Result<Breakpoint, String> {
+        if idx >= self.breakpoints.len() {
+            return Err(format!("There is no breakpoint {}!", idx));
+        }
+        self.held.remove(idx);
+        Ok(self.breakpoints.remove(idx))
+    }
+
+    fn holds(&self, breakpoint: &Breakpoint) -> bool {
+        match *breakpoint {
+            Breakpoint::Cycle(cycle) => self.cpu.cycle() + 1 == cycle,
+            Breakpoint::Instruction(idx) => {
+                !self.cpu.is_halted() && self.cpu.pc() == idx && self.cpu.elapsed() == 0
+            }
+            Breakpoint::Register {
+                register,
+                comparison,
+                value,
+            } => comparison.holds(self.cpu.register(register), value),
+        }
+    }
+
+    // The first breakpoint to stop at before the next cycle.
+    fn hit(&mut self) -> Option<usize> {
+        let mut hit = None;
+        for idx in 0..self.breakpoints.len() {
+            let holds = self.holds(&self.breakpoints[idx]);
+            let is_condition = matches!(self.breakpoints[idx], Breakpoint::Register { .. });
+            if holds && !(is_condition && self.held[idx]) && hit.is_none() {
+                hit = Some(idx);
+            }
+            self.held[idx] = holds;
+        }
+        hit
+    }
+
+    fn tick(&mut self) -> bool {
+        self.cpu.tick(&mut Observers {
+            crt: &mut self.crt,
+            trace: &mut self.trace,
+        })
+    }
+
+    // Runs the given number of cycles, ignoring the breakpoints.
+    pub fn step(&mut self, cycles: u64) -> Stop {
+        for _ in 0..cycles {
+            if !self.tick() {
+                return Stop::Halted;
+            }
+        }
+        self.hit();
+        match self.cpu.is_halted() {
+            true => Stop::Halted,
+            false => Stop::Stepped,
+        }
+    }
+
+    // Runs until the program is done or the screen is drawn, ignoring the
+    // breakpoints.
+    pub fn run(&mut self) -> Stop {
+        self.cpu.run(&mut Observers {
+            crt: &mut self.crt,
+            trace: &mut self.trace,
+        });
+        self.hit();
+        match self.cpu.is_halted() {
+            true => Stop::Halted,
+            false => Stop::Stepped,
+        }
+    }
+
+    // Runs until a breakpoint is hit, the program is done, the screen gets
+    // drawn or the cycle budget runs out. Once the screen is drawn only the
+    // other ones stop it.
+    pub fn resume(&mut self) -> Stop {
+        let drawn = self.crt.is_done();
+        for _ in 0..self.budget {
+            if self.stopped_at != Some(self.cpu.cycle()) {
+                if let Some(idx) = self.hit() {
+                    self.stopped_at = Some(self.cpu.cycle());
+                    return Stop::Breakpoint(idx);
+                }
+            }
+            if !self.tick() {
+                return Stop::Halted;
+            }
+            if !drawn && self.crt.is_done() {
+                self.hit();
+                return Stop::ScreenDone;
+            }
+        }
+        self.hit();
+        match self.cpu.is_halted() {
+            true => Stop::Halted,
+            false => Stop::Budget,
+        }
+    }
+
+    // The state before the next cycle: the instruction, the registers and
+    // the sprite above the column the beam is about to draw.
+    pub fn status(&self) -> String {
+        let cpu = &self.cpu;
+        let next = cpu.cycle() + 1;
+        let mut status = match cpu.current() {
+            Some(instruction) => format!(
+                "Cycle {}, instruction {}: {} ({}/{} cycles done)\n",
+                next,
+                cpu.pc(),
+                instruction,
+                cpu.elapsed(),
+                cpu.costs().get(instruction.opcode())
+            ),
+            None => format!("Halted after {} cycles\n", cpu.cycle()),
+        };
+        let registers = Reg::ALL
+            .iter()
+            .map(|&register| format!("{}={}", register, cpu.register(register)))
+            .collect::<Vec<_>>();
+        status.push_str(&format!("{}\n", registers.join(" ")));
+        let sprite = (0..self.crt.width())
+            .map(|col| match self.crt.sprite_covers(cpu.x(), col) {
+                true => '#',
+                false => '.',
+            })
+            .collect::<String>();
+        status.push_str(&format!("sprite {}\n", sprite));
+        if let (false, Some((row, col))) = (cpu.is_halted(), self.crt.position(next)) {
+            let lit = self.crt.sprite_covers(cpu.x(), col);
+            status.push_str(&format!(
+                "beam   {}^ row {}, col {}, {}\n",
+                " ".repeat(col),
+                row,
+                col,
+                if lit { "lit" } else { "dark" }
+            ));
+        }
+        status
+    }
+}
+
+// Commands of the interactive debugger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Step(u64),
+    Continue,
+    Break(Breakpoint),
+    Delete(usize),
+    Breakpoints,
+    Print,
+    Screen,
+    Trace(String),
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (command, argument) = match s.split_once(' ') {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (s, ""),
+        };
+        let missing = || format!("{} expects an argument!", command);
+        let number = |n: &str| {
+            n.parse::<u64>()
+                .map_err(|_| format!("{} is not a number!", n))
+        };
+        match command {
+            "s" | "step" if argument.is_empty() => Ok(Command::Step(1)),
+            "s" | "step" => Ok(Command::Step(number(argument)?)),
+            "c" | "continue" => Ok(Command::Continue),
+            "b" | "break" if argument.is_empty() => Err(missing()),
+            "b" | "break" => Ok(Command::Break(argument.parse()?)),
+            "d" | "delete" if argument.is_empty() => Err(missing()),
+            "d" | "delete" => Ok(Command::Delete(number(argument)? as usize)),
+            "i" | "breakpoints" => Ok(Command::Breakpoints),
+            "p" | "print" => Ok(Command::Print),
+            "screen" => Ok(Command::Screen),
+            "trace" if argument.is_empty() => Err(missing()),
+            "trace" => Ok(Command::Trace(argument.to_owned())),
+            "h" | "help" => Ok(Command::Help),
+            "q" | "quit" => Ok(Command::Quit),
+            _ => Err(format!("Unknown command {}, try help", command)),
+        }
+    }
+}
+
+pub const HELP: &str = "\
+step [n]        (s) runs n cycles, 1 by default
+continue        (c) runs until a breakpoint, the end, the last pixel or the budget
+break <bp>      (b) adds a breakpoint: cycle=<n>, instr=<n> or e.g. x>=10
+delete <n>      (d) removes a breakpoint
+breakpoints     (i) lists the breakpoints
+print           (p) shows the registers, the sprite and the beam
+screen          draws the screen so far
+trace <file>    writes the cycles so far as CSV (cycle,x,pixel,lit)
+quit            (q)
+";
@@ -1,72 +1,164 @@
-use std::io::{BufRead, BufReader};
+use day_10_puzzle_1::asm::{assemble, disassemble};
+use day_10_puzzle_1::cpu::{Cpu, CycleCosts};
+use day_10_puzzle_1::debugger::{Breakpoint, Command, Debugger, Stop, HELP};
+use day_10_puzzle_1::signal::SignalStrength;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
 use std::str::FromStr;
-use std::{fs::File, io};
+use std::{fs, io};
 
-type Register = i64;
-
-enum Instruction {
-    Noop,
-    Addx { value: Register },
-}
-
-impl FromStr for Instruction {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(" ");
-        let instruction = split.next().expect("Empty instruction!");
-        if instruction == "noop" {
-            return Ok(Instruction::Noop);
-        } else if instruction == "addx" {
-            return Ok(Instruction::Addx {
-                value: split
-                    .next()
-                    .expect("Expected an argument!")
-                    .parse::<Register>()
-                    .map_err(|err| err.to_string())?,
-            });
+// Usage: day_10_puzzle_1 <input> [--costs <instruction>=<cycles>,...] [--disassemble]
+//        day_10_puzzle_1 <input> --debug [--break <breakpoint>]... [--budget <cycles>] [--costs ...]
+//        day_10_puzzle_1 <input> --trace <file.csv> [--costs ...]
+// The debugger reads its commands from the standard input, see `help`. The
+// trace stops once the program halts or the screen is drawn and keeps at most
+// the first 100000 cycles. `continue` runs a million cycles at most unless
+// --budget says otherwise.
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut costs = CycleCosts::default();
+    let mut print_program = false;
+    let mut debug = false;
+    let mut breakpoints = vec![];
+    let mut trace = None;
+    let mut budget = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--costs" => {
+                costs = CycleCosts::from_str(&args.next().expect("No cycle costs given!"))
+                    .expect("Couldn't read the cycle costs!")
+            }
+            "--disassemble" => print_program = true,
+            "--debug" => debug = true,
+            "--break" => breakpoints
+                .push(Breakpoint::from_str(&args.next().expect("No breakpoint given!")).unwrap()),
+            "--budget" => {
+                budget = Some(
+                    args.next()
+                        .expect("No cycle budget given!")
+                        .parse()
+                        .expect("The cycle budget isn't a number!"),
+                )
+            }
+            "--trace" => trace = Some(args.next().expect("No trace file given!")),
+            _ => panic!("Unknown argument {}!", arg),
         }
-        Err(format!("Unknown instruction {}!", instruction))
     }
-}
+    let program = assemble(&fs::read_to_string(input_path)?).expect("Couldn't read the program!");
 
-fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-
-    let mut register_trace = vec![1 as Register];
-    for result_line in reader.lines() {
-        let line = result_line.expect("Couldn't read a line!");
-        let instruction = Instruction::from_str(&line)
-            .expect(&format!("Couldn't read the instruction: {}", line));
+    if print_program {
+        print!("{}", disassemble(&program));
+        return Ok(());
+    }
 
-        let last_register_value = *register_trace.last().unwrap();
-        match instruction {
-            Instruction::Noop => register_trace.push(last_register_value),
-            Instruction::Addx { value } => {
-                register_trace.push(last_register_value);
-                register_trace.push(last_register_value + value);
+    if debug || trace.is_some() {
+        let mut debugger = Debugger::new(Cpu::with_costs(program, costs));
+        for breakpoint in breakpoints {
+            debugger.add_breakpoint(breakpoint);
+        }
+        if let Some(budget) = budget {
+            debugger.set_budget(budget);
+        }
+        match trace {
+            Some(path) => {
+                debugger.run();
+                write_trace(&debugger, &path)?;
             }
+            None => run_debugger(&mut debugger)?,
         }
+        return Ok(());
     }
 
-    let sum_signal_strength: Register = register_trace
-        .iter()
-        .enumerate()
-        .skip(19)
-        .step_by(40)
-        .map(|(idx, register)| {
-            let cycle = idx + 1;
-            if cycle < 221 {
-                *register * cycle as Register
-            } else {
-                0
-            }
-        })
-        .sum();
+    let mut signal = SignalStrength::default();
+    Cpu::with_costs(program, costs).run(&mut signal);
 
-    println!("Sum signal strength: {}", sum_signal_strength);
+    println!("Sum signal strength: {}", signal.sum());
+
+    Ok(())
+}
 
+fn write_trace(debugger: &Debugger, path: &str) -> io::Result<()> {
+    debugger
+        .trace()
+        .write_csv(BufWriter::new(File::create(path)?))?;
+    let trace = debugger.trace();
+    println!("Written {} cycles to {}", trace.rows().len(), path);
+    if trace.dropped() > 0 {
+        println!("The {} cycles after them were left out", trace.dropped());
+    }
     Ok(())
 }
+
+fn run_debugger(debugger: &mut Debugger) -> io::Result<()> {
+    print!("{}", debugger.status());
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("(dbg) ");
+        io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            println!();
+            return Ok(());
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match Command::from_str(&line) {
+            Ok(command) => command,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+        let stop = match command {
+            Command::Step(cycles) => debugger.step(cycles),
+            Command::Continue => debugger.resume(),
+            Command::Break(breakpoint) => {
+                let idx = debugger.add_breakpoint(breakpoint);
+                println!("Breakpoint {}: {}", idx, breakpoint);
+                continue;
+            }
+            Command::Delete(idx) => {
+                match debugger.remove_breakpoint(idx) {
+                    Ok(breakpoint) => println!("Removed breakpoint {}: {}", idx, breakpoint),
+                    Err(err) => println!("{}", err),
+                }
+                continue;
+            }
+            Command::Breakpoints => {
+                for (idx, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                    println!("{}: {}", idx, breakpoint);
+                }
+                continue;
+            }
+            Command::Print => {
+                print!("{}", debugger.status());
+                continue;
+            }
+            Command::Screen => {
+                print!("{}", debugger.crt().render());
+                continue;
+            }
+            Command::Trace(path) => {
+                if let Err(err) = write_trace(debugger, &path) {
+                    println!("Couldn't write the trace: {}", err);
+                }
+                continue;
+            }
+            Command::Help => {
+                print!("{}", HELP);
+                continue;
+            }
+            Command::Quit => return Ok(()),
+        };
+        match stop {
+            Stop::Breakpoint(idx) => {
+                println!("Breakpoint {}: {}", idx, debugger.breakpoints()[idx])
+            }
+            Stop::ScreenDone => println!("The screen is drawn"),
+            Stop::Budget => println!("No breakpoint hit within the cycle budget"),
+            Stop::Halted | Stop::Stepped => {}
+        }
+        print!("{}", debugger.status());
+    }
+}
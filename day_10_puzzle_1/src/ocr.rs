@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+// glyphs are separated by a single dark column
+const GLYPH_PITCH: usize = GLYPH_WIDTH + 1;
+
+// The capital letters the CRT is known to draw, row by row. Letters nobody
+// has seen it draw are left out, they read as unknown glyphs.
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 16] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn font() -> HashMap<String, char> {
+    FONT.iter()
+        .map(|(letter, rows)| (rows.concat(), *letter))
+        .collect()
+}
+
+// Reads the letters off a screen drawn with # and . (e.g. `Crt::render`).
+// A dark glyph reads as a space, trailing ones are dropped. All the glyphs
+// which are not in the font are reported together with their position.
+pub fn read_text(screen: &str) -> Result<String, String> {
+    let rows = screen
+        .lines()
+        .map(str::trim_end)
+        .filter(|row| !row.is_empty())
+        .collect::<Vec<_>>();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(format!(
+            "Expected {} rows of pixels, got {}!",
+            GLYPH_HEIGHT,
+            rows.len()
+        ));
+    }
+    let width = rows[0].len();
+    if let Some(row) = rows.iter().position(|row| row.len() != width) {
+        return Err(format!(
+            "Row {} is {} pixels wide instead of {}!",
+            row + 1,
+            rows[row].len(),
+            width
+        ));
+    }
+    if let Some(pixel) = rows
+        .iter()
+        .flat_map(|row| row.chars())
+        .find(|&c| c != '#' && c != '.')
+    {
+        return Err(format!("Unexpected pixel {:?}!", pixel));
+    }
+
+    let font = font();
+    let mut text = String::new();
+    let mut unknown = vec![];
+    for (idx, start) in (0..width).step_by(GLYPH_PITCH).enumerate() {
+        if start + GLYPH_WIDTH > width {
+            return Err(format!(
+                "The screen is {} pixels wide, that's not a whole number of glyphs!",
+                width
+            ));
+        }
+        let glyph = rows
+            .iter()
+            .map(|row| &row[start..start + GLYPH_WIDTH])
+            .collect::<Vec<_>>();
+        match font.get(&glyph.concat()) {
+            Some(&letter) => text.push(letter),
+            None if glyph.iter().all(|row| !row.contains('#')) => text.push(' '),
+            None => {
+                unknown.push(format!(
+                    "Unknown glyph {} at column {}:\n{}",
+                    idx + 1,
+                    start,
+                    glyph.join("\n")
+                ));
+                text.push('?');
+            }
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(format!(
+            "{}\nRead so far: {}",
+            unknown.join("\n"),
+            text.trim_end()
+        ));
+    }
+    Ok(text.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::cpu::Cpu;
+    use crate::crt::Crt;
+
+    const README: &str = include_str!("../README.md");
+
+    fn screen_of(source: &str) -> String {
+        let mut crt = Crt::new();
+        Cpu::new(assemble(source).unwrap()).run(&mut crt);
+        crt.render()
+    }
+
+    // The larger example program, the second code block of the README.
+    fn example_program() -> String {
+        README.split("```").nth(3).unwrap().trim().to_owned()
+    }
+
+    #[test]
+    fn example_screen_is_not_text() {
+        let screen = screen_of(&example_program());
+        assert_eq!(
+            screen,
+            "##..##..##..##..##..##..##..##..##..##..\n\
+             ###...###...###...###...###...###...###.\n\
+             ####....####....####....####....####....\n\
+             #####.....#####.....#####.....#####.....\n\
+             ######......######......######......####\n\
+             #######.......#######.......#######.....\n"
+        );
+        let err = read_text(&screen).unwrap_err();
+        assert!(err.ends_with("Read so far: ????????"), "{}", err);
+    }
+
+    #[test]
+    fn reads_the_puzzle_answer() {
+        let screen = screen_of(include_str!("../input"));
+        assert_eq!(read_text(&screen), Ok("PZULBAUA".to_owned()));
+    }
+
+    #[test]
+    fn every_glyph_reads_back() {
+        for (letter, rows) in FONT {
+            let screen = rows.map(|row| format!("{}.", row)).join("\n");
+            assert_eq!(read_text(&screen), Ok(letter.to_string()));
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::cpu::{Cpu, Observer, Register};
+
+// Sums the signal strength, the cycle number times X, during the 20th cycle
+// and every 40 cycles after that up to the 220th.
+pub struct SignalStrength {
+    pub first: u64,
+    pub every: u64,
+    pub last: u64,
+    sum: Register,
+    // last cycle seen
+    cycle: u64,
+}
+
+impl SignalStrength {
+    pub fn new(first: u64, every: u64, last: u64) -> Self {
+        Self {
+            first,
+            every,
+            last,
+            sum: 0,
+            cycle: 0,
+        }
+    }
+
+    pub fn sum(&self) -> Register {
+        self.sum
+    }
+}
+
+impl Default for SignalStrength {
+    fn default() -> Self {
+        Self::new(20, 40, 220)
+    }
+}
+
+impl Observer for SignalStrength {
+    fn during(&mut self, cycle: u64, cpu: &Cpu) {
+        self.cycle = cycle;
+        if cycle >= self.first
+            && cycle <= self.last
+            && (cycle - self.first).is_multiple_of(self.every)
+        {
+            // X wraps around, so does the sum
+            self.sum = self
+                .sum
+                .wrapping_add((cycle as Register).wrapping_mul(cpu.x()));
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.cycle >= self.last
+    }
+}
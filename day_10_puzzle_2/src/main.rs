@@ -1,68 +1,78 @@
-use std::io::{BufRead, BufReader};
+use day_10_puzzle_1::asm::assemble;
+use day_10_puzzle_1::cpu::{Cpu, CycleCosts};
+use day_10_puzzle_1::crt::{Crt, Geometry, Wrap};
+use day_10_puzzle_1::ocr::read_text;
+use std::fs::File;
+use std::io::BufWriter;
 use std::str::FromStr;
-use std::{fs::File, io};
+use std::{fs, io};
 
-type Register = i64;
-
-enum Instruction {
-    Noop,
-    Addx { value: Register },
-}
-
-impl FromStr for Instruction {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(" ");
-        let instruction = split.next().expect("Empty instruction!");
-        if instruction == "noop" {
-            return Ok(Instruction::Noop);
-        } else if instruction == "addx" {
-            return Ok(Instruction::Addx {
-                value: split
-                    .next()
-                    .expect("Expected an argument!")
-                    .parse::<Register>()
-                    .map_err(|err| err.to_string())?,
-            });
-        }
-        Err(format!("Unknown instruction {}!", instruction))
-    }
+fn number(arg: Option<String>, flag: &str) -> usize {
+    arg.and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| panic!("{} expects a number!", flag))
 }
 
+// Usage: day_10_puzzle_2 <input> [options]
+// Options:
+//   --costs <instruction>=<cycles>,...   cycles taken by the instructions
+//   --width <n> --height <n>             size of the screen, 40x6 by default
+//   --sprite <n>                         width of the sprite, 3 by default
+//   --wrap clip|around                   what happens to the sprite at the edges of a row
+//   --render text|ansi|pbm               how to show the screen, text by default
+//   --output <file>                      where to write the PBM image, stdout by default
+//   --scale <n>                          pixels per CRT pixel in the PBM image
 fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-
-    let mut register_trace = vec![1 as Register];
-    for result_line in reader.lines() {
-        let line = result_line.expect("Couldn't read a line!");
-        let instruction = Instruction::from_str(&line)
-            .expect(&format!("Couldn't read the instruction: {}", line));
-
-        let last_register_value = *register_trace.last().unwrap();
-        match instruction {
-            Instruction::Noop => register_trace.push(last_register_value),
-            Instruction::Addx { value } => {
-                register_trace.push(last_register_value);
-                register_trace.push(last_register_value + value);
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut costs = CycleCosts::default();
+    let mut geometry = Geometry::default();
+    let mut render = "text".to_owned();
+    let mut output = None;
+    let mut scale = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--costs" => {
+                costs = CycleCosts::from_str(&args.next().expect("No cycle costs given!"))
+                    .expect("Couldn't read the cycle costs!")
+            }
+            "--width" => geometry.width = number(args.next(), &arg),
+            "--height" => geometry.height = number(args.next(), &arg),
+            "--sprite" => geometry.sprite_width = number(args.next(), &arg),
+            "--wrap" => {
+                geometry.wrap = Wrap::from_str(&args.next().expect("No wrap mode given!")).unwrap()
             }
+            "--render" => render = args.next().expect("No render mode given!"),
+            "--output" => output = Some(args.next().expect("No output file given!")),
+            "--scale" => scale = number(args.next(), &arg).max(1),
+            _ => panic!("Unknown argument {}!", arg),
         }
     }
+    let program = assemble(&fs::read_to_string(input_path)?).expect("Couldn't read the program!");
 
-    let mut register = register_trace.iter();
-    for _ in 0..6 {
-        let mut crt_line = String::new();
-        for pixel_idx in 0..40 {
-            let sprite_pos = *register.next().unwrap_or(&0);
-            if pixel_idx >= sprite_pos - 1 && pixel_idx <= sprite_pos + 1 {
-                crt_line.push('#');
-            } else {
-                crt_line.push('.');
+    let mut crt = Crt::with_geometry(geometry).expect("Couldn't set up the screen!");
+    Cpu::with_costs(program, costs).run(&mut crt);
+
+    match render.as_str() {
+        "text" => {
+            let screen = crt.render();
+            print!("{}", screen);
+            // the font only fits the puzzle's screen
+            if geometry == Geometry::default() {
+                match read_text(&screen) {
+                    Ok(text) => println!("Result: {}", text),
+                    Err(err) => eprintln!("Couldn't read the screen: {}", err),
+                }
             }
         }
-        println!("{}", crt_line);
+        "ansi" => print!("{}", crt.render_ansi()),
+        "pbm" => match output {
+            Some(path) => {
+                crt.write_pbm(BufWriter::new(File::create(&path)?), scale)?;
+                println!("Written {}", path);
+            }
+            None => crt.write_pbm(io::stdout().lock(), scale)?,
+        },
+        _ => panic!("Unknown render mode {}!", render),
     }
 
     Ok(())
@@ -0,0 +1,272 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Sub => 1,
+            BinOp::Mul | BinOp::Div => 2,
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            BinOp::Add => '+',
+            BinOp::Sub => '-',
+            BinOp::Mul => '*',
+            BinOp::Div => '/',
+        }
+    }
+}
+
+// How worry levels are computed with, plain numbers or e.g. numbers modulo
+// something. Operations that can't be done give an error.
+pub trait Arithmetic {
+    type Value: Clone;
+
+    fn constant(&self, value: u64) -> Result<Self::Value, String>;
+
+    fn apply(&self, op: BinOp, lhs: Self::Value, rhs: Self::Value) -> Result<Self::Value, String>;
+
+    fn is_multiple_of(&self, value: &Self::Value, divisor: u64) -> bool;
+
+    // Whether the operation can be done at all, so unusable notes are turned
+    // down before anything is computed.
+    fn supports(&self, _op: BinOp) -> bool {
+        true
+    }
+}
+
+// Right hand side of a monkey's "new = ..." operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Const(u64),
+    Binary {
+        op: BinOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+impl Expr {
+    pub fn eval<A: Arithmetic>(&self, old: &A::Value, arithmetic: &A) -> Result<A::Value, String> {
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Const(value) => arithmetic.constant(*value),
+            Expr::Binary { op, lhs, rhs } => {
+                arithmetic.apply(*op, lhs.eval(old, arithmetic)?, rhs.eval(old, arithmetic)?)
+            }
+        }
+    }
+
+    // Whether any part of the expression is the given operation.
+    pub fn uses(&self, op: BinOp) -> bool {
+        match self {
+            Expr::Old | Expr::Const(_) => false,
+            Expr::Binary { op: own, lhs, rhs } => *own == op || lhs.uses(op) || rhs.uses(op),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary { op, .. } => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+// Prints the expression with the parentheses it needs only, reading it
+// back gives the same expression.
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::Binary { op, lhs, rhs } => {
+                // operators are left associative, so the right side needs
+                // parentheses on the same level too
+                if lhs.precedence() < op.precedence() {
+                    write!(f, "({})", lhs)?;
+                } else {
+                    write!(f, "{}", lhs)?;
+                }
+                write!(f, " {} ", op.symbol())?;
+                if rhs.precedence() <= op.precedence() {
+                    write!(f, "({})", rhs)
+                } else {
+                    write!(f, "{}", rhs)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Old,
+    Number(u64),
+    Op(BinOp),
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '+' => Token::Op(BinOp::Add),
+            '-' => Token::Op(BinOp::Sub),
+            '*' => Token::Op(BinOp::Mul),
+            '/' => Token::Op(BinOp::Div),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ if c.is_ascii_alphanumeric() => {
+                let mut end = pos + c.len_utf8();
+                while let Some(&(next, c)) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+                match &s[pos..end] {
+                    "old" => Token::Old,
+                    word => Token::Number(word.parse().map_err(|_| {
+                        format!("Expected old or a number at {}, got {:?}", pos + 1, word)
+                    })?),
+                }
+            }
+            _ => return Err(format!("Unexpected {:?} at {}", c, pos + 1)),
+        };
+        tokens.push((pos, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    // length of the text, where the errors about a missing end point to
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.next).map(|&(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |&(pos, _)| pos) + 1
+    }
+
+    // sum = product (("+" | "-") product)*
+    // product = factor (("*" | "/") factor)*
+    fn binary(&mut self, precedence: u8) -> Result<Expr, String> {
+        let mut lhs = match precedence {
+            1 => self.binary(2)?,
+            _ => self.factor()?,
+        };
+        while let Some(Token::Op(op)) = self.peek() {
+            if op.precedence() != precedence {
+                break;
+            }
+            self.next += 1;
+            let rhs = match precedence {
+                1 => self.binary(2)?,
+                _ => self.factor()?,
+            };
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+        Ok(lhs)
+    }
+
+    // factor = "old" | number | "(" sum ")"
+    fn factor(&mut self) -> Result<Expr, String> {
+        let position = self.position();
+        let token = self.peek();
+        self.next += 1;
+        match token {
+            Some(Token::Old) => Ok(Expr::Old),
+            Some(Token::Number(value)) => Ok(Expr::Const(value)),
+            Some(Token::Open) => {
+                let expr = self.binary(1)?;
+                match self.peek() {
+                    Some(Token::Close) => {
+                        self.next += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(format!("Expected ) at {}", self.position())),
+                }
+            }
+            Some(_) => Err(format!("Expected old, a number or ( at {}", position)),
+            None => Err(format!("Unexpected end at {}", position)),
+        }
+    }
+}
+
+// Supports + - * / on old and non-negative integer constants, with the usual
+// precedence and parentheses.
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            next: 0,
+            end: s.len(),
+        };
+        let expr = parser.binary(1)?;
+        if parser.peek().is_some() {
+            return Err(format!("Unexpected text at {}", parser.position()));
+        }
+        Ok(expr)
+    }
+}
+
+// Worry levels as plain numbers, failing on overflow, on going below zero
+// and on division by zero. Division rounds down.
+pub struct Plain;
+
+impl Arithmetic for Plain {
+    type Value = u64;
+
+    fn constant(&self, value: u64) -> Result<u64, String> {
+        Ok(value)
+    }
+
+    fn apply(&self, op: BinOp, lhs: u64, rhs: u64) -> Result<u64, String> {
+        let result = match op {
+            BinOp::Add => lhs.checked_add(rhs),
+            BinOp::Sub => lhs.checked_sub(rhs),
+            BinOp::Mul => lhs.checked_mul(rhs),
+            BinOp::Div => lhs.checked_div(rhs),
+        };
+        result.ok_or_else(|| {
+            format!(
+                "Worry level {} {} {} is out of range!",
+                lhs,
+                op.symbol(),
+                rhs
+            )
+        })
+    }
+
+    fn is_multiple_of(&self, value: &u64, divisor: u64) -> bool {
+        value.is_multiple_of(divisor)
+    }
+}
@@ -0,0 +1,205 @@
+use crate::expr::Arithmetic;
+use crate::simulation::Simulation;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Where an item goes round after round. Its rounds all start from its
+// monkey and worry level at the start of the round, so once one of these
+// repeats the item goes through the same rounds over and over.
+struct Journey {
+    // rounds before the repeating ones
+    start: usize,
+    period: usize,
+    // inspections of every monkey after 0, 1, ... start + period rounds,
+    // a row of the monkeys' counts after another
+    totals: Vec<u64>,
+}
+
+// Every item followed on its own until its rounds repeat, which gives the
+// inspections after any number of rounds without playing them.
+pub struct Journeys {
+    monkeys: usize,
+    // inspections before the journeys start
+    base: Vec<u64>,
+    journeys: Vec<Journey>,
+}
+
+impl Journeys {
+    // Follows the items the monkeys hold now, so the rounds are counted from
+    // the simulation's rounds so far. The worry levels must take finitely
+    // many values for this to end, e.g. modulo something.
+    pub fn new<A: Arithmetic>(simulation: &Simulation<A>) -> Result<Self, String>
+    where
+        A::Value: Eq + Hash,
+    {
+        let monkeys = simulation.monkeys().len();
+        let mut journeys = vec![];
+        for (idx, monkey) in simulation.monkeys().iter().enumerate() {
+            for item in &monkey.items {
+                journeys.push(
+                    follow(simulation, idx, item.clone())
+                        .map_err(|err| format!("Item {}: {}", journeys.len() + 1, err))?,
+                );
+            }
+        }
+        Ok(Self {
+            monkeys,
+            base: simulation.inspections(),
+            journeys,
+        })
+    }
+
+    // Most rounds an item takes before its rounds repeat and the longest
+    // repeat.
+    pub fn longest_cycle(&self) -> (usize, usize) {
+        self.journeys
+            .iter()
+            .fold((0, 0), |(start, period), journey| {
+                (start.max(journey.start), period.max(journey.period))
+            })
+    }
+
+    // Inspections of every monkey after the rounds.
+    pub fn inspections(&self, rounds: u64) -> Result<Vec<u64>, String> {
+        let mut inspections = self.base.clone();
+        for journey in &self.journeys {
+            let row = |round: usize| &journey.totals[round * self.monkeys..][..self.monkeys];
+            let (round, cycles) = match rounds.checked_sub(journey.start as u64) {
+                Some(after_start) => (
+                    journey.start + (after_start % journey.period as u64) as usize,
+                    after_start / journey.period as u64,
+                ),
+                None => (rounds as usize, 0),
+            };
+            let (first, last) = (row(journey.start), row(journey.start + journey.period));
+            for (monkey, count) in inspections.iter_mut().enumerate() {
+                *count = cycles
+                    .checked_mul(last[monkey] - first[monkey])
+                    .and_then(|repeated| repeated.checked_add(row(round)[monkey]))
+                    .and_then(|journey| journey.checked_add(*count))
+                    .ok_or_else(|| {
+                        format!(
+                            "The inspections after {} rounds don't fit in {} bits!",
+                            rounds,
+                            u64::BITS
+                        )
+                    })?;
+            }
+        }
+        Ok(inspections)
+    }
+}
+
+fn follow<A: Arithmetic>(
+    simulation: &Simulation<A>,
+    idx: usize,
+    worry: A::Value,
+) -> Result<Journey, String>
+where
+    A::Value: Eq + Hash,
+{
+    let monkeys = simulation.monkeys().len();
+    let mut seen = HashMap::new();
+    let mut state = (idx, worry);
+    let mut totals = vec![0; monkeys];
+    loop {
+        let round = totals.len() / monkeys - 1;
+        if let Some(&start) = seen.get(&state) {
+            return Ok(Journey {
+                start,
+                period: round - start,
+                totals,
+            });
+        }
+        seen.insert(state.clone(), round);
+
+        let mut counts = totals[round * monkeys..].to_vec();
+        let (mut idx, mut worry) = state;
+        // the item stays in the round while it's thrown to monkeys that
+        // haven't had their turn yet
+        loop {
+            counts[idx] += 1;
+            let (target, new_worry) = simulation.inspect(idx, &worry)?;
+            let next_round = target <= idx;
+            (idx, worry) = (target, new_worry);
+            if next_round {
+                break;
+            }
+        }
+        totals.extend(counts);
+        state = (idx, worry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notes::parse_notes;
+    use crate::simulation::monkey_business;
+    use crate::worry::Modular;
+
+    const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn matches_playing_the_rounds() {
+        let notes = parse_notes(EXAMPLE).unwrap();
+        let new_simulation =
+            || Simulation::new(&notes, Modular::for_notes(&notes).unwrap(), None).unwrap();
+        let journeys = Journeys::new(&new_simulation()).unwrap();
+
+        // around where every item starts repeating and where it's done it once
+        let mut rounds = vec![0, 1, 20, 1000, 10000];
+        for journey in &journeys.journeys {
+            for round in [journey.start, journey.start + journey.period] {
+                rounds.extend([round.saturating_sub(1), round, round + 1]);
+            }
+        }
+        let (start, period) = journeys.longest_cycle();
+        rounds.extend([start + period, start + 3 * period + 7]);
+        rounds.sort_unstable();
+        rounds.dedup();
+
+        let mut simulation = new_simulation();
+        for rounds in rounds.into_iter().map(|rounds| rounds as u64) {
+            simulation.run(rounds - simulation.rounds()).unwrap();
+            assert_eq!(
+                journeys.inspections(rounds).unwrap(),
+                simulation.inspections(),
+                "after {} rounds",
+                rounds
+            );
+        }
+        assert_eq!(
+            monkey_business(&journeys.inspections(10000).unwrap()),
+            2713310158
+        );
+    }
+}
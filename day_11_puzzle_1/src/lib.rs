@@ -0,0 +1,6 @@
+pub mod expr;
+pub mod itemwise;
+pub mod notes;
+pub mod simulation;
+pub mod trace;
+pub mod worry;
@@ -1,139 +1,99 @@
-use std::collections::{BTreeMap, HashMap};
-use std::error;
-use std::io::{BufRead, BufReader};
+use day_11_puzzle_1::expr::{Arithmetic, Plain};
+use day_11_puzzle_1::notes::{parse_notes, MonkeyNotes};
+use day_11_puzzle_1::simulation::Simulation;
+use day_11_puzzle_1::trace::{RoundSet, Tracer};
+use day_11_puzzle_1::worry::Exact;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufWriter;
 use std::str::FromStr;
-use std::{fs::File, io};
+use std::{fs, io, process};
 
-type FnType = Box<dyn Fn(i32) -> i32>;
-
-struct Monkey {
-    items: Vec<i32>,
-    operation: FnType,
-    test: FnType,
-    inspected_counter: i32,
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
 
-impl FromStr for Monkey {
-    type Err = Box<dyn error::Error>;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(";");
-
-        let aaa = &split.next().expect("Expected items list!")[16..];
-        let items: Vec<i32> = aaa
-            .split(", ")
-            .map(|item| item.parse::<i32>().expect("Couldn't parse item!"))
-            .collect();
-
-        let mut op_split = split.next().expect("Wrong operation format!")[21..].split(" ");
-        let operator: fn(i32, i32) -> i32 =
-            match op_split.next().expect("Expected to get an operator!") {
-                "+" => |x: i32, y: i32| x + y,
-                "*" => |x: i32, y: i32| x * y,
-                _ => panic!("Unknown operator!"),
-            };
-        let operation_arg = op_split
-            .next()
-            .expect("Expected to get an operation argument!")
-            .to_owned();
-        let operation = Box::new(move |x: i32| -> i32 {
-            if operation_arg == "old" {
-                return operator(x, x);
-            }
-            let arg = operation_arg
-                .parse::<i32>()
-                .expect("Couldn't parse the operation arg!");
-            return operator(x, arg);
-        });
-
-        let divisor = split.next().expect("Expected to get a divisor!")[19..].parse::<i32>()?;
-        let true_id = split
-            .next()
-            .expect("Expected to get Monkey Id for a true case")[25..]
-            .parse::<i32>()?;
-        let false_id = split
-            .next()
-            .expect("Expected to get Monkey Id for a false case")[26..]
-            .parse::<i32>()?;
-        let test = Box::new(move |x: i32| {
-            if x % divisor == 0 {
-                return true_id;
-            }
-            false_id
-        });
-
-        Ok(Self {
-            items,
-            operation,
-            test,
-            inspected_counter: 0,
-        })
+fn monkey_business<A: Arithmetic>(
+    notes: &[MonkeyNotes],
+    arithmetic: A,
+    rounds: u64,
+    tracer: &mut Tracer,
+) -> Result<u128, String>
+where
+    A::Value: Display,
+{
+    let mut simulation = Simulation::new(notes, arithmetic, Some(3))?;
+    for _ in 0..rounds {
+        simulation.round_with(tracer)?;
+        print!(
+            "{}",
+            tracer.after_round(simulation.rounds(), simulation.monkeys())
+        );
     }
+    Ok(simulation.monkey_business())
 }
 
+// Usage: day_11_puzzle_1 <input> [options]
+// Options:
+//   --rounds <n>           rounds to play, 20 by default
+//   --exact                keep the worry levels exact however big they get
+//   --report <rounds>      show the items held and the inspections after the rounds,
+//                          e.g. 1,20 or 1..20 or 1000..10000/1000 or /5 or all
+//   --throws               show every throw during the reported rounds too
+//   --stats <file.csv>     write the inspections of every monkey in every round
+//   --graph <file.csv>     write how many items went from each monkey to another
 fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-
-    let mut monkeys = BTreeMap::<i32, Monkey>::new();
-    while let Some(Ok(line)) = lines.next() {
-        if line.starts_with("Monkey") {
-            let end_of_id = line.len() - 1;
-            let id: i32 = line[7..end_of_id]
-                .parse()
-                .expect("Couldn't read monkey's id!");
-            let mut monkey_description = String::new();
-            while let Some(Ok(line)) = lines.next() {
-                if line.is_empty() {
-                    break;
-                }
-                monkey_description += &(line.trim().to_owned() + ";");
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut rounds = 20;
+    let mut exact = false;
+    let mut report = RoundSet::default();
+    let mut throws = false;
+    let mut stats_path = None;
+    let mut graph_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => {
+                rounds = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--rounds expects a number!")
             }
-            monkeys.insert(
-                id,
-                Monkey::from_str(&monkey_description).expect("Couldn't parse the monkey!"),
-            );
+            "--exact" => exact = true,
+            "--report" => {
+                report = RoundSet::from_str(&args.next().expect("No rounds given!"))
+                    .unwrap_or_else(|err| exit_with(&err))
+            }
+            "--throws" => throws = true,
+            "--stats" => stats_path = Some(args.next().expect("No statistics file given!")),
+            "--graph" => graph_path = Some(args.next().expect("No graph file given!")),
+            _ => panic!("Unknown argument {}!", arg),
         }
     }
+    let notes = parse_notes(&fs::read_to_string(input_path)?)
+        .unwrap_or_else(|err| exit_with(&format!("Couldn't parse the notes: {}", err)));
 
-    let monkey_ids: Vec<i32> = monkeys.keys().map(|x| x.clone()).collect();
-    for _ in 0..20 {
-        for id in &monkey_ids {
-            let mut moved_items = HashMap::<i32, Vec<i32>>::new();
-            {
-                let monkey = monkeys.get_mut(id).unwrap();
-                for item in &monkey.items {
-                    let worry_level = (monkey.operation)(*item) / 3;
-                    let pass_to_monkey = (monkey.test)(worry_level);
-                    moved_items
-                        .entry(pass_to_monkey)
-                        .or_default()
-                        .push(worry_level);
-                    monkey.inspected_counter += 1;
-                }
-                monkey.items.clear();
-            }
-            for (other_monkey_id, items) in moved_items.iter_mut() {
-                monkeys.entry(*other_monkey_id).and_modify(|other_monkey| {
-                    other_monkey.items.append(items);
-                });
-            }
+    let mut tracer = Tracer::new(report, throws, stats_path.is_some() || graph_path.is_some());
+    let result = match exact {
+        true => monkey_business(&notes, Exact, rounds, &mut tracer),
+        false => monkey_business(&notes, Plain, rounds, &mut tracer)
+            .map_err(|err| format!("{} (--exact keeps worry levels of any size)", err)),
+    };
+    match result {
+        Ok(monkey_business) => println!("Monkey business: {}", monkey_business),
+        Err(err) => exit_with(&err),
+    }
+    if let Some(statistics) = tracer.statistics() {
+        if let Some(path) = stats_path {
+            statistics.write_inspections_csv(BufWriter::new(File::create(&path)?))?;
+            println!("Written {}", path);
+        }
+        if let Some(path) = graph_path {
+            statistics.write_transfers_csv(BufWriter::new(File::create(&path)?))?;
+            println!("Written {}", path);
         }
     }
 
-    let mut inspected_items_counters: Vec<i32> = monkeys
-        .iter()
-        .map(|(_, monkey)| monkey.inspected_counter)
-        .collect();
-
-    inspected_items_counters.sort();
-
-    println!(
-        "Monkey business: {}",
-        inspected_items_counters.iter().rev().take(2).product::<i32>()
-    );
-
     Ok(())
 }
@@ -0,0 +1,200 @@
+use crate::expr::Expr;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+pub type MonkeyId = usize;
+
+// Everything the notes say about a monkey.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonkeyNotes {
+    pub id: MonkeyId,
+    pub items: Vec<u64>,
+    // what "new" is
+    pub operation: Expr,
+    pub divisor: u64,
+    pub if_true: MonkeyId,
+    pub if_false: MonkeyId,
+}
+
+// Fields of a monkey block, the value of a field is the text after its label.
+const FIELDS: [&str; 5] = ["Starting items", "Operation", "Test", "If true", "If false"];
+
+// Reads the text after the expected words, e.g. "divisible by 13" with the
+// words ["divisible", "by"] gives "13". Any spacing goes, words only have to
+// be apart where two of them would run together, so "new=old" is fine.
+fn after_words<'a>(value: &'a str, words: &[&str]) -> Result<&'a str, String> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut rest = value.trim_start();
+    for word in words {
+        rest = rest
+            .strip_prefix(word)
+            .filter(|rest| !word.ends_with(is_word_char) || !rest.starts_with(is_word_char))
+            .ok_or_else(|| format!("Expected {:?}, got {:?}", words.join(" "), value.trim()))?
+            .trim_start();
+    }
+    Ok(rest.trim_end())
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Expected a number, got {:?}", value))
+}
+
+// Fills a field, every field is given once only.
+fn set<T>(field: &mut Option<T>, value: T) -> Result<(), String> {
+    match field {
+        Some(_) => Err("Given twice".to_owned()),
+        None => {
+            *field = Some(value);
+            Ok(())
+        }
+    }
+}
+
+fn parse_field(field: &str, value: &str, monkey: &mut Partial) -> Result<(), String> {
+    match field {
+        "Starting items" => set(
+            &mut monkey.items,
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(number)
+                .collect::<Result<_, _>>()?,
+        ),
+        "Operation" => {
+            let expr = after_words(value, &["new", "="])?;
+            set(
+                &mut monkey.operation,
+                expr.parse()
+                    .map_err(|err| format!("{} of {:?}", err, expr))?,
+            )
+        }
+        "Test" => set(
+            &mut monkey.divisor,
+            number(after_words(value, &["divisible", "by"])?)?,
+        ),
+        "If true" => set(
+            &mut monkey.if_true,
+            number(after_words(value, &["throw", "to", "monkey"])?)?,
+        ),
+        "If false" => set(
+            &mut monkey.if_false,
+            number(after_words(value, &["throw", "to", "monkey"])?)?,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+// A monkey block being read.
+#[derive(Default)]
+struct Partial {
+    id: MonkeyId,
+    line: usize,
+    items: Option<Vec<u64>>,
+    operation: Option<Expr>,
+    divisor: Option<u64>,
+    if_true: Option<MonkeyId>,
+    if_false: Option<MonkeyId>,
+}
+
+impl Partial {
+    fn finish(self) -> Result<MonkeyNotes, String> {
+        let missing = |field: &str| {
+            format!(
+                "Line {}: Monkey {} has no {:?} field!",
+                self.line, self.id, field
+            )
+        };
+        let divisor = self.divisor.ok_or_else(|| missing(FIELDS[2]))?;
+        if divisor == 0 {
+            return Err(format!(
+                "Line {}: Monkey {} tests divisibility by 0!",
+                self.line, self.id
+            ));
+        }
+        Ok(MonkeyNotes {
+            id: self.id,
+            items: self.items.clone().ok_or_else(|| missing(FIELDS[0]))?,
+            operation: self.operation.clone().ok_or_else(|| missing(FIELDS[1]))?,
+            divisor,
+            if_true: self.if_true.ok_or_else(|| missing(FIELDS[3]))?,
+            if_false: self.if_false.ok_or_else(|| missing(FIELDS[4]))?,
+        })
+    }
+}
+
+// Reads the notes block by block. Every block starts with "Monkey <id>:"
+// followed by its fields in any order, indentation and blank lines don't
+// matter. The monkeys come out sorted by their ids, which must be unique and
+// every monkey must throw to a monkey from the notes.
+pub fn parse_notes(s: &str) -> Result<Vec<MonkeyNotes>, String> {
+    let mut monkeys = vec![];
+    let mut current: Option<Partial> = None;
+    for (idx, line) in s.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (label, value) = line.split_once(':').ok_or_else(|| {
+            format!(
+                "Line {}: Expected <field>: <value>, got {:?}",
+                line_number, line
+            )
+        })?;
+        let label = label.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(id) = label.strip_prefix("Monkey ") {
+            if !value.trim().is_empty() {
+                return Err(format!(
+                    "Line {}: Unexpected {:?} after the monkey's id",
+                    line_number,
+                    value.trim()
+                ));
+            }
+            if let Some(monkey) = current.take() {
+                monkeys.push(monkey.finish()?);
+            }
+            current = Some(Partial {
+                id: number(id)
+                    .map_err(|err| format!("Line {}: Monkey id: {}", line_number, err))?,
+                line: line_number,
+                ..Default::default()
+            });
+            continue;
+        }
+        let field = FIELDS
+            .iter()
+            .find(|field| **field == label)
+            .ok_or_else(|| format!("Line {}: Unknown field {:?}", line_number, label))?;
+        let monkey = current
+            .as_mut()
+            .ok_or_else(|| format!("Line {}: {} outside of a monkey block", line_number, field))?;
+        parse_field(field, value, monkey)
+            .map_err(|err| format!("Line {}: {}: {}", line_number, field, err))?;
+    }
+    if let Some(monkey) = current {
+        monkeys.push(monkey.finish()?);
+    }
+
+    monkeys.sort_by_key(|monkey| monkey.id);
+    let ids = monkeys
+        .iter()
+        .map(|monkey| monkey.id)
+        .collect::<HashSet<_>>();
+    if ids.len() != monkeys.len() {
+        return Err("Some monkeys share an id!".to_owned());
+    }
+    for monkey in &monkeys {
+        for target in [monkey.if_true, monkey.if_false] {
+            if !ids.contains(&target) {
+                return Err(format!(
+                    "Monkey {} throws to monkey {} which isn't in the notes!",
+                    monkey.id, target
+                ));
+            }
+        }
+    }
+    Ok(monkeys)
+}
@@ -0,0 +1,182 @@
+use crate::expr::{Arithmetic, BinOp, Expr};
+use crate::notes::{MonkeyId, MonkeyNotes};
+
+// An item thrown from one monkey to another, with its new worry level.
+pub struct Throw<'a, V> {
+    pub round: u64,
+    pub from: MonkeyId,
+    pub to: MonkeyId,
+    pub worry: &'a V,
+}
+
+// Told about every throw while the monkeys play.
+pub trait Observer<V> {
+    fn thrown(&mut self, throw: &Throw<V>);
+}
+
+impl<V> Observer<V> for () {
+    fn thrown(&mut self, _throw: &Throw<V>) {}
+}
+
+impl<V, O: Observer<V>> Observer<V> for &mut O {
+    fn thrown(&mut self, throw: &Throw<V>) {
+        (**self).thrown(throw);
+    }
+}
+
+pub struct Monkey<V> {
+    pub id: MonkeyId,
+    pub items: Vec<V>,
+    pub inspections: u64,
+    operation: Expr,
+    divisor: u64,
+    // indexes of the monkeys thrown to, not their ids
+    if_true: usize,
+    if_false: usize,
+}
+
+// The monkeys playing keep-away, with the worry levels computed by the
+// arithmetic and divided by the relief after every inspection if there is any.
+pub struct Simulation<A: Arithmetic> {
+    arithmetic: A,
+    relief: Option<u64>,
+    monkeys: Vec<Monkey<A::Value>>,
+    rounds: u64,
+}
+
+impl<A: Arithmetic> Simulation<A> {
+    pub fn new(notes: &[MonkeyNotes], arithmetic: A, relief: Option<u64>) -> Result<Self, String> {
+        if relief.is_some() && !arithmetic.supports(BinOp::Div) {
+            return Err(
+                "Relief divides the worry levels, which the arithmetic can't do!".to_owned(),
+            );
+        }
+        let index = |id: MonkeyId| {
+            notes
+                .iter()
+                .position(|monkey| monkey.id == id)
+                .ok_or_else(|| format!("There's no monkey {}!", id))
+        };
+        let mut monkeys = vec![];
+        for monkey in notes {
+            for op in [BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div] {
+                if monkey.operation.uses(op) && !arithmetic.supports(op) {
+                    return Err(format!(
+                        "Monkey {} uses {} in {}, which the arithmetic can't do!",
+                        monkey.id,
+                        op.symbol(),
+                        monkey.operation
+                    ));
+                }
+            }
+            monkeys.push(Monkey {
+                id: monkey.id,
+                items: monkey
+                    .items
+                    .iter()
+                    .map(|&item| arithmetic.constant(item))
+                    .collect::<Result<_, _>>()?,
+                inspections: 0,
+                operation: monkey.operation.clone(),
+                divisor: monkey.divisor,
+                if_true: index(monkey.if_true)?,
+                if_false: index(monkey.if_false)?,
+            });
+        }
+        Ok(Self {
+            arithmetic,
+            relief,
+            monkeys,
+            rounds: 0,
+        })
+    }
+
+    pub fn monkeys(&self) -> &[Monkey<A::Value>] {
+        &self.monkeys
+    }
+
+    // Rounds played so far.
+    pub fn rounds(&self) -> u64 {
+        self.rounds
+    }
+
+    // Every monkey in turn inspects and throws all the items it has, items
+    // thrown to a monkey later in the round are inspected by it in this round
+    // too.
+    pub fn round(&mut self) -> Result<(), String> {
+        self.round_with(&mut ())
+    }
+
+    // Index of the monkey the item is thrown to by the monkey at `idx` and its
+    // worry level after the inspection.
+    pub(crate) fn inspect(&self, idx: usize, item: &A::Value) -> Result<(usize, A::Value), String> {
+        let monkey = &self.monkeys[idx];
+        let in_monkey = |err| format!("Monkey {}: {}", monkey.id, err);
+        let mut worry = monkey
+            .operation
+            .eval(item, &self.arithmetic)
+            .map_err(in_monkey)?;
+        if let Some(relief) = self.relief {
+            worry = self
+                .arithmetic
+                .apply(BinOp::Div, worry, self.arithmetic.constant(relief)?)
+                .map_err(in_monkey)?;
+        }
+        let target = match self.arithmetic.is_multiple_of(&worry, monkey.divisor) {
+            true => monkey.if_true,
+            false => monkey.if_false,
+        };
+        Ok((target, worry))
+    }
+
+    pub fn round_with<O: Observer<A::Value>>(&mut self, observer: &mut O) -> Result<(), String> {
+        for idx in 0..self.monkeys.len() {
+            let items = std::mem::take(&mut self.monkeys[idx].items);
+            self.monkeys[idx].inspections += items.len() as u64;
+            for item in items {
+                let (target, worry) = self
+                    .inspect(idx, &item)
+                    .map_err(|err| format!("Round {}: {}", self.rounds + 1, err))?;
+                observer.thrown(&Throw {
+                    round: self.rounds + 1,
+                    from: self.monkeys[idx].id,
+                    to: self.monkeys[target].id,
+                    worry: &worry,
+                });
+                self.monkeys[target].items.push(worry);
+            }
+        }
+        self.rounds += 1;
+        Ok(())
+    }
+
+    pub fn run(&mut self, rounds: u64) -> Result<(), String> {
+        for _ in 0..rounds {
+            self.round()?;
+        }
+        Ok(())
+    }
+
+    pub fn inspections(&self) -> Vec<u64> {
+        self.monkeys
+            .iter()
+            .map(|monkey| monkey.inspections)
+            .collect()
+    }
+
+    pub fn monkey_business(&self) -> u128 {
+        monkey_business(&self.inspections())
+    }
+}
+
+// The inspections of the two most active monkeys multiplied.
+pub fn monkey_business(inspections: &[u64]) -> u128 {
+    let mut inspections = inspections.to_vec();
+    inspections.sort_unstable();
+    inspections
+        .iter()
+        .rev()
+        .take(2)
+        .map(|&count| count as u128)
+        .product()
+}
@@ -0,0 +1,210 @@
+use crate::notes::MonkeyId;
+use crate::simulation::{Monkey, Observer, Throw};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rounds {
+    Single(u64),
+    // every step'th round from first up to last
+    Range { first: u64, last: u64, step: u64 },
+}
+
+// Rounds to look at, e.g. "1,20,1000..10000/1000". An entry is a round, a
+// range "a..b" (both ends included, "a.." has no end), a range with a step
+// "a..b/k" or "/k" for every k'th round. "all" is every round.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoundSet {
+    entries: Vec<Rounds>,
+}
+
+impl RoundSet {
+    pub fn all() -> Self {
+        Self {
+            entries: vec![Rounds::Range {
+                first: 1,
+                last: u64::MAX,
+                step: 1,
+            }],
+        }
+    }
+
+    pub fn contains(&self, round: u64) -> bool {
+        self.entries.iter().any(|&entry| match entry {
+            Rounds::Single(single) => single == round,
+            Rounds::Range { first, last, step } => {
+                (first..=last).contains(&round) && (round - first).is_multiple_of(step)
+            }
+        })
+    }
+}
+
+impl FromStr for RoundSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "all" {
+            return Ok(Self::all());
+        }
+        let number = |n: &str| {
+            n.parse::<u64>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Expected a round number, got {:?}", n))
+        };
+        let mut entries = vec![];
+        for entry in s.split(',').map(str::trim) {
+            let (range, step) = match entry.split_once('/') {
+                Some((range, step)) => (range, number(step)?),
+                None => (entry, 1),
+            };
+            entries.push(match range.split_once("..") {
+                Some((first, last)) => Rounds::Range {
+                    first: number(first)?,
+                    last: match last {
+                        "" => u64::MAX,
+                        _ => number(last)?,
+                    },
+                    step,
+                },
+                None if range.is_empty() => Rounds::Range {
+                    first: step,
+                    last: u64::MAX,
+                    step,
+                },
+                None if !entry.contains('/') => Rounds::Single(number(range)?),
+                None => return Err(format!("A step needs a range, got {:?}", entry)),
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
+// Inspections over time and how many items went between every two monkeys.
+#[derive(Clone, Debug, Default)]
+pub struct Statistics {
+    ids: Vec<MonkeyId>,
+    // inspections of every monkey so far, after each round
+    totals: Vec<Vec<u64>>,
+    transfers: BTreeMap<(MonkeyId, MonkeyId), u64>,
+}
+
+impl Statistics {
+    pub fn record<V>(&mut self, monkeys: &[Monkey<V>]) {
+        if self.ids.is_empty() {
+            self.ids = monkeys.iter().map(|monkey| monkey.id).collect();
+        }
+        self.totals
+            .push(monkeys.iter().map(|monkey| monkey.inspections).collect());
+    }
+
+    pub fn add_transfer(&mut self, from: MonkeyId, to: MonkeyId) {
+        *self.transfers.entry((from, to)).or_default() += 1;
+    }
+
+    pub fn transfers(&self) -> &BTreeMap<(MonkeyId, MonkeyId), u64> {
+        &self.transfers
+    }
+
+    // A line per round and monkey with the inspections during the round and
+    // the ones so far.
+    pub fn write_inspections_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "round,monkey,inspections,total")?;
+        let mut previous = vec![0; self.ids.len()];
+        for (idx, totals) in self.totals.iter().enumerate() {
+            for ((id, total), previous) in self.ids.iter().zip(totals).zip(&mut previous) {
+                writeln!(writer, "{},{},{},{}", idx + 1, id, total - *previous, total)?;
+                *previous = *total;
+            }
+        }
+        writer.flush()
+    }
+
+    // The circulation graph as a list of edges, items thrown from a monkey to
+    // another over all the rounds.
+    pub fn write_transfers_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "from,to,items")?;
+        for ((from, to), items) in &self.transfers {
+            writeln!(writer, "{},{},{}", from, to, items)?;
+        }
+        writer.flush()
+    }
+}
+
+// Writes the reports for the chosen rounds and collects the statistics while
+// the monkeys play.
+#[derive(Clone, Debug, Default)]
+pub struct Tracer {
+    report: RoundSet,
+    throws: bool,
+    statistics: Option<Statistics>,
+    // throws of the round being played
+    pending: Vec<String>,
+}
+
+impl Tracer {
+    // Reports the items held and the inspections after the rounds, with every
+    // throw during them if `throws` is set.
+    pub fn new(report: RoundSet, throws: bool, statistics: bool) -> Self {
+        Self {
+            report,
+            throws,
+            statistics: statistics.then(Statistics::default),
+            pending: vec![],
+        }
+    }
+
+    pub fn statistics(&self) -> Option<&Statistics> {
+        self.statistics.as_ref()
+    }
+
+    // Call after every round, gives the report of the round or nothing if it
+    // isn't one of the chosen ones.
+    pub fn after_round<V: Display>(&mut self, round: u64, monkeys: &[Monkey<V>]) -> String {
+        if let Some(statistics) = self.statistics.as_mut() {
+            statistics.record(monkeys);
+        }
+        if !self.report.contains(round) {
+            return String::new();
+        }
+        let mut report = format!("== After round {} ==\n", round);
+        for line in self.pending.drain(..) {
+            report.push_str(&line);
+            report.push('\n');
+        }
+        for monkey in monkeys {
+            let items = monkey
+                .items
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            report.push_str(format!("Monkey {}: {}", monkey.id, items).trim_end());
+            report.push('\n');
+        }
+        for monkey in monkeys {
+            report.push_str(&format!(
+                "Monkey {} inspected items {} times.\n",
+                monkey.id, monkey.inspections
+            ));
+        }
+        report.push('\n');
+        report
+    }
+}
+
+impl<V: Display> Observer<V> for Tracer {
+    fn thrown(&mut self, throw: &Throw<V>) {
+        if let Some(statistics) = self.statistics.as_mut() {
+            statistics.add_transfer(throw.from, throw.to);
+        }
+        if self.throws && self.report.contains(throw.round) {
+            self.pending.push(format!(
+                "Monkey {} throws an item with worry level {} to monkey {}.",
+                throw.from, throw.worry, throw.to
+            ));
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::expr::{Arithmetic, BinOp};
+use crate::notes::MonkeyNotes;
+use num_bigint::BigUint;
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+// Worry levels modulo a multiple of every monkey's divisor, which keeps them
+// small and doesn't change where any item goes. Dividing them makes no sense.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Modular {
+    modulus: u64,
+}
+
+impl Modular {
+    // Works modulo the least common multiple of the divisors.
+    pub fn for_divisors<I: IntoIterator<Item = u64>>(divisors: I) -> Result<Self, String> {
+        let mut modulus: u64 = 1;
+        for divisor in divisors {
+            if divisor == 0 {
+                return Err("Can't keep worry levels modulo 0!".to_owned());
+            }
+            modulus = (modulus / gcd(modulus, divisor))
+                .checked_mul(divisor)
+                .ok_or_else(|| {
+                    format!(
+                        "The least common multiple of the divisors doesn't fit in {} bits!",
+                        u64::BITS
+                    )
+                })?;
+        }
+        Ok(Self { modulus })
+    }
+
+    pub fn for_notes(notes: &[MonkeyNotes]) -> Result<Self, String> {
+        Self::for_divisors(notes.iter().map(|monkey| monkey.divisor))
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+}
+
+impl Arithmetic for Modular {
+    type Value = u64;
+
+    fn constant(&self, value: u64) -> Result<u64, String> {
+        Ok(value % self.modulus)
+    }
+
+    fn apply(&self, op: BinOp, lhs: u64, rhs: u64) -> Result<u64, String> {
+        // both sides are below the modulus, so none of this overflows u128
+        let (lhs, rhs, modulus) = (lhs as u128, rhs as u128, self.modulus as u128);
+        let result = match op {
+            BinOp::Add => lhs + rhs,
+            BinOp::Sub => lhs + modulus - rhs,
+            BinOp::Mul => lhs * rhs,
+            BinOp::Div => {
+                return Err(format!(
+                    "Can't divide worry levels kept modulo {}!",
+                    self.modulus
+                ))
+            }
+        };
+        Ok((result % modulus) as u64)
+    }
+
+    // Right for the divisors the modulus was made of only.
+    fn is_multiple_of(&self, value: &u64, divisor: u64) -> bool {
+        value.is_multiple_of(divisor)
+    }
+
+    fn supports(&self, op: BinOp) -> bool {
+        op != BinOp::Div
+    }
+}
+
+// Worry levels as exact numbers of any size, failing on going below zero and
+// on division by zero only. Division rounds down.
+pub struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+
+    fn constant(&self, value: u64) -> Result<BigUint, String> {
+        Ok(BigUint::from(value))
+    }
+
+    fn apply(&self, op: BinOp, lhs: BigUint, rhs: BigUint) -> Result<BigUint, String> {
+        match op {
+            BinOp::Add => Ok(lhs + rhs),
+            BinOp::Sub if lhs >= rhs => Ok(lhs - rhs),
+            BinOp::Mul => Ok(lhs * rhs),
+            BinOp::Div if rhs != BigUint::ZERO => Ok(lhs / rhs),
+            _ => Err(format!(
+                "Worry level {} {} {} is out of range!",
+                lhs,
+                op.symbol(),
+                rhs
+            )),
+        }
+    }
+
+    fn is_multiple_of(&self, value: &BigUint, divisor: u64) -> bool {
+        value % divisor == BigUint::ZERO
+    }
+}
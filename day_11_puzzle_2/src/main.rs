@@ -1,187 +1,137 @@
-use std::collections::{BTreeMap, HashMap, VecDeque};
-use std::error;
-use std::io::{BufRead, BufReader};
-use std::ops::{Add, Mul, Rem};
+use day_11_puzzle_1::itemwise::Journeys;
+use day_11_puzzle_1::notes::parse_notes;
+use day_11_puzzle_1::simulation::{monkey_business, Simulation};
+use day_11_puzzle_1::trace::{RoundSet, Tracer};
+use day_11_puzzle_1::worry::Modular;
+use std::fs::File;
+use std::io::BufWriter;
 use std::str::FromStr;
-use std::{fs::File, io};
+use std::{fs, io, process};
 
-// base value that contains all the common divisors from the input
-const BASE_VALUE: u64 = 2 * 3 * 5 * 7 * 9 * 11 * 13 * 17 * 19;
-
-#[derive(Clone)]
-struct WorryLevel {
-    value: u64,
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
 
-impl From<u64> for WorryLevel {
-    fn from(u: u64) -> Self {
-        Self {
-            value: u % BASE_VALUE,
+// Plays the rounds one by one and checks the item-wise simulation gives the
+// same inspections after every one of them.
+fn verify(mut simulation: Simulation<Modular>, journeys: &Journeys, rounds: u64) {
+    for round in 1..=rounds {
+        simulation.round().unwrap_or_else(|err| exit_with(&err));
+        let expected = simulation.inspections();
+        let got = journeys
+            .inspections(round)
+            .unwrap_or_else(|err| exit_with(&err));
+        if got != expected {
+            exit_with(&format!(
+                "Round {}: the item-wise simulation gives the inspections {:?}, playing the rounds gives {:?}!",
+                round, got, expected
+            ));
         }
     }
+    println!(
+        "The item-wise simulation agrees with playing the rounds for rounds 1 to {}.",
+        rounds
+    );
 }
 
-impl Add for WorryLevel {
-    type Output = WorryLevel;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            value: (self.value + rhs.value) % BASE_VALUE,
+// Usage: day_11_puzzle_2 <input> [options]
+// Options:
+//   --rounds <n>           rounds to play, 10000 by default
+//   --report <rounds>      show the items held and the inspections after the rounds,
+//                          e.g. 1,20,1000..10000/1000, the worry levels are shown
+//                          modulo the divisors' least common multiple
+//   --throws               show every throw during the reported rounds too
+//   --stats <file.csv>     write the inspections of every monkey in every round
+//   --graph <file.csv>     write how many items went from each monkey to another
+//   --itemwise             follow every item on its own until it repeats its rounds,
+//                          which works for any number of rounds, e.g. 1000000000000
+//   --verify <n>           check the item-wise simulation against playing rounds 1 to n
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut rounds = 10000;
+    let mut report = RoundSet::default();
+    let mut throws = false;
+    let mut stats_path = None;
+    let mut graph_path = None;
+    let mut itemwise = false;
+    let mut verify_rounds = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => {
+                rounds = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--rounds expects a number!")
+            }
+            "--report" => {
+                report = RoundSet::from_str(&args.next().expect("No rounds given!"))
+                    .unwrap_or_else(|err| exit_with(&err))
+            }
+            "--throws" => throws = true,
+            "--stats" => stats_path = Some(args.next().expect("No statistics file given!")),
+            "--graph" => graph_path = Some(args.next().expect("No graph file given!")),
+            "--itemwise" => itemwise = true,
+            "--verify" => {
+                verify_rounds = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .expect("--verify expects a number!"),
+                )
+            }
+            _ => panic!("Unknown argument {}!", arg),
         }
     }
-}
-
-impl Mul for WorryLevel {
-    type Output = WorryLevel;
-
-    fn mul(self, rhs: Self) -> Self::Output {
-        Self {
-            value: (self.value * rhs.value) % BASE_VALUE,
+    let notes = parse_notes(&fs::read_to_string(input_path)?)
+        .unwrap_or_else(|err| exit_with(&format!("Couldn't parse the notes: {}", err)));
+
+    // no relief any more, so the worry levels are kept modulo the divisors'
+    // least common multiple
+    let arithmetic = Modular::for_notes(&notes).unwrap_or_else(|err| exit_with(&err));
+    let mut simulation =
+        Simulation::new(&notes, arithmetic, None).unwrap_or_else(|err| exit_with(&err));
+
+    if itemwise || verify_rounds.is_some() {
+        let journeys = Journeys::new(&simulation).unwrap_or_else(|err| exit_with(&err));
+        let (start, period) = journeys.longest_cycle();
+        println!(
+            "Every item repeats its rounds after at most {} rounds, at most every {} rounds.",
+            start, period
+        );
+        if let Some(verify_rounds) = verify_rounds {
+            verify(simulation, &journeys, verify_rounds);
+            return Ok(());
         }
+        let inspections = journeys
+            .inspections(rounds)
+            .unwrap_or_else(|err| exit_with(&err));
+        println!("Monkey business: {}", monkey_business(&inspections));
+        return Ok(());
     }
-}
-
-impl Rem<u64> for WorryLevel {
-    type Output = u64;
-
-    fn rem(self, rhs: u64) -> Self::Output {
-        self.value % rhs
-    }
-}
-
-struct Monkey {
-    items: VecDeque<WorryLevel>,
-    operation: Box<dyn Fn(WorryLevel) -> WorryLevel>,
-    test: Box<dyn Fn(WorryLevel) -> i32>,
-    inspected_counter: u64,
-}
-
-impl FromStr for Monkey {
-    type Err = Box<dyn error::Error>;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(";");
-
-        let items: VecDeque<WorryLevel> = split.next().expect("Expected items list!")[16..]
-            .split(", ")
-            .map(|item| WorryLevel::from(item.parse::<u64>().expect("Couldn't parse single item")))
-            .collect();
-
-        let mut op_split = split.next().expect("Wrong operation format!")[21..].split(" ");
-        let operator: fn(WorryLevel, WorryLevel) -> WorryLevel =
-            match op_split.next().expect("Expected to get an operator!") {
-                "+" => |x: WorryLevel, y: WorryLevel| x + y,
-                "*" => |x: WorryLevel, y: WorryLevel| x * y,
-                _ => panic!("Unknown operator!"),
-            };
-        let operation_arg = op_split
-            .next()
-            .expect("Expected to get an operation argument!")
-            .to_owned();
-        let operation = Box::new(move |x: WorryLevel| -> WorryLevel {
-            if operation_arg == "old" {
-                return operator(x.clone(), x);
-            }
-            let arg = WorryLevel::from(
-                operation_arg
-                    .parse::<u64>()
-                    .expect("Parsing the big int has failed!"),
-            );
-            return operator(x, arg);
-        });
-
-        let divisor = u64::from_str(&split.next().expect("Expected to get a divisor!")[19..])
-            .expect("Parsing the divisor has failed!");
-        let true_id = split
-            .next()
-            .expect("Expected to get Monkey Id for a true case")[25..]
-            .parse::<i32>()?;
-        let false_id = split
-            .next()
-            .expect("Expected to get Monkey Id for a false case")[26..]
-            .parse::<i32>()?;
-        let test = Box::new(move |x: WorryLevel| {
-            if x % divisor == 0 {
-                return true_id;
-            }
-            false_id
-        });
-
-        Ok(Self {
-            items,
-            operation,
-            test,
-            inspected_counter: 0,
-        })
+    let mut tracer = Tracer::new(report, throws, stats_path.is_some() || graph_path.is_some());
+    for _ in 0..rounds {
+        simulation
+            .round_with(&mut tracer)
+            .unwrap_or_else(|err| exit_with(&err));
+        print!(
+            "{}",
+            tracer.after_round(simulation.rounds(), simulation.monkeys())
+        );
     }
-}
+    println!("Monkey business: {}", simulation.monkey_business());
 
-fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-
-    let mut monkeys = BTreeMap::<i32, Monkey>::new();
-    while let Some(Ok(line)) = lines.next() {
-        if line.starts_with("Monkey") {
-            let end_of_id = line.len() - 1;
-            let id: i32 = line[7..end_of_id]
-                .parse()
-                .expect("Couldn't read monkey's id!");
-            let mut monkey_description = String::new();
-            while let Some(Ok(line)) = lines.next() {
-                if line.is_empty() {
-                    break;
-                }
-                monkey_description += &(line.trim().to_owned() + ";");
-            }
-            monkeys.insert(
-                id,
-                Monkey::from_str(&monkey_description).expect("Couldn't parse the monkey!"),
-            );
+    if let Some(statistics) = tracer.statistics() {
+        if let Some(path) = stats_path {
+            statistics.write_inspections_csv(BufWriter::new(File::create(&path)?))?;
+            println!("Written {}", path);
         }
-    }
-
-    let monkey_ids: Vec<i32> = monkeys.keys().map(|x| x.clone()).collect();
-    for _ in 0..10000 {
-        for id in &monkey_ids {
-            let mut moved_items = HashMap::<i32, VecDeque<WorryLevel>>::new();
-            {
-                let monkey = monkeys.get_mut(id).unwrap();
-                while let Some(item) = monkey.items.pop_front() {
-                    let worry_level = (monkey.operation)(item);
-                    let pass_to_monkey = (monkey.test)(worry_level.clone());
-                    moved_items
-                        .entry(pass_to_monkey)
-                        .or_default()
-                        .push_back(worry_level);
-                    monkey.inspected_counter += 1;
-                }
-            }
-            for (other_monkey_id, items) in moved_items.iter_mut() {
-                monkeys.entry(*other_monkey_id).and_modify(|other_monkey| {
-                    other_monkey.items.append(items);
-                });
-            }
+        if let Some(path) = graph_path {
+            statistics.write_transfers_csv(BufWriter::new(File::create(&path)?))?;
+            println!("Written {}", path);
         }
     }
 
-    let mut inspected_items_counters: Vec<u64> = monkeys
-        .iter()
-        .map(|(_, monkey)| monkey.inspected_counter)
-        .collect();
-
-    inspected_items_counters.sort();
-
-    println!(
-        "Monkey business: {}",
-        inspected_items_counters
-            .iter()
-            .rev()
-            .take(2)
-            .product::<u64>()
-    );
-
     Ok(())
 }
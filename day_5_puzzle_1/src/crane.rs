@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// Stacks keyed by their label, every stack is ordered from the bottom to the top crate.
+pub type Stacks = BTreeMap<usize, Vec<char>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub count: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let mut field = |keyword: &str| -> Result<usize, String> {
+            if parts.next() != Some(keyword) {
+                return Err(format!("Expected '{}' in '{}'!", keyword, s));
+            }
+            parts
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("Expected a number after '{}' in '{}'!", keyword, s))
+        };
+        let count = field("move")?;
+        let from = field("from")?;
+        let to = field("to")?;
+        if parts.next().is_some() {
+            return Err(format!("Unexpected trailing text in '{}'!", s));
+        }
+        Ok(Self { count, from, to })
+    }
+}
+
+// Decides in which order the lifted crates land on the destination stack.
+pub trait MovePolicy {
+    fn name(&self) -> &'static str;
+
+    // Gets the crates as they were lying on the source stack (bottom to top)
+    // and puts them into the order they end up on the destination stack.
+    fn arrange(&self, crates: &mut [char]);
+
+    // Reverts `arrange`, used when a move is undone.
+    fn restore(&self, crates: &mut [char]);
+}
+
+// Moves crates one at a time, so the moved slice ends up reversed.
+pub struct CrateMover9000;
+
+impl MovePolicy for CrateMover9000 {
+    fn name(&self) -> &'static str {
+        "CrateMover 9000"
+    }
+
+    fn arrange(&self, crates: &mut [char]) {
+        crates.reverse();
+    }
+
+    fn restore(&self, crates: &mut [char]) {
+        crates.reverse();
+    }
+}
+
+// Moves all the crates at once, so their order is retained.
+pub struct CrateMover9001;
+
+impl MovePolicy for CrateMover9001 {
+    fn name(&self) -> &'static str {
+        "CrateMover 9001"
+    }
+
+    fn arrange(&self, _crates: &mut [char]) {}
+
+    fn restore(&self, _crates: &mut [char]) {}
+}
+
+pub fn policy_for_model(model: &str) -> Result<Box<dyn MovePolicy>, String> {
+    match model {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        _ => Err(format!("Unknown crane model {}!", model)),
+    }
+}
+
+// A single executed move, holds everything that is needed to undo it.
+#[derive(Clone, Debug)]
+pub struct MoveRecord {
+    pub line: usize,
+    pub instruction: Instruction,
+    // crates as they land on the destination stack, bottom to top
+    pub crates: Vec<char>,
+}
+
+pub struct Crane {
+    policy: Box<dyn MovePolicy>,
+    stacks: Stacks,
+    history: Vec<MoveRecord>,
+    // number of moves from the history that are currently applied
+    position: usize,
+}
+
+impl Crane {
+    pub fn new(policy: Box<dyn MovePolicy>, stacks: Stacks) -> Self {
+        Self {
+            policy,
+            stacks,
+            history: vec![],
+            position: 0,
+        }
+    }
+
+    pub fn policy(&self) -> &dyn MovePolicy {
+        self.policy.as_ref()
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Executes the instruction read from the given line of the input.
+    // Any moves that were undone before are dropped from the history.
+    pub fn execute(&mut self, line: usize, instruction: Instruction) -> Result<(), String> {
+        let Instruction { count, from, to } = instruction;
+        if !self.stacks.contains_key(&to) {
+            return Err(format!("Line {}: there is no stack {}!", line, to));
+        }
+        let source = self
+            .stacks
+            .get_mut(&from)
+            .ok_or_else(|| format!("Line {}: there is no stack {}!", line, from))?;
+        if count > source.len() {
+            return Err(format!(
+                "Line {}: can't move {} crates from stack {} that holds only {}!",
+                line,
+                count,
+                from,
+                source.len()
+            ));
+        }
+        let mut crates = source.split_off(source.len() - count);
+        self.policy.arrange(&mut crates);
+        self.stacks.get_mut(&to).unwrap().extend_from_slice(&crates);
+
+        self.history.truncate(self.position);
+        self.history.push(MoveRecord {
+            line,
+            instruction,
+            crates,
+        });
+        self.position += 1;
+        Ok(())
+    }
+
+    // Reverts the last applied move, returns false if there is nothing to undo.
+    pub fn step_back(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        let record = &self.history[self.position];
+        let Instruction { count, from, to } = record.instruction;
+        let target = self.stacks.get_mut(&to).unwrap();
+        let mut crates = target.split_off(target.len() - count);
+        self.policy.restore(&mut crates);
+        self.stacks.get_mut(&from).unwrap().append(&mut crates);
+        true
+    }
+
+    // Re-applies the next recorded move, returns false if there is nothing to redo.
+    pub fn step_forward(&mut self) -> bool {
+        let Some(record) = self.history.get(self.position) else {
+            return false;
+        };
+        let Instruction { count, from, to } = record.instruction;
+        let source = self.stacks.get_mut(&from).unwrap();
+        source.truncate(source.len() - count);
+        self.stacks
+            .get_mut(&to)
+            .unwrap()
+            .extend_from_slice(&record.crates);
+        self.position += 1;
+        true
+    }
+
+    // Replays the history so the stacks show the state after the first `k` moves.
+    pub fn seek(&mut self, k: usize) -> Result<(), String> {
+        if k > self.history.len() {
+            return Err(format!(
+                "Can't seek to move {}, only {} moves were recorded!",
+                k,
+                self.history.len()
+            ));
+        }
+        while self.position > k {
+            self.step_back();
+        }
+        while self.position < k {
+            self.step_forward();
+        }
+        Ok(())
+    }
+
+    // Top crate of every stack, empty stacks are skipped.
+    pub fn top_crates(&self) -> String {
+        self.stacks
+            .values()
+            .filter_map(|stack| stack.last())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVES: [&str; 4] = [
+        "move 1 from 2 to 1",
+        "move 3 from 1 to 3",
+        "move 2 from 2 to 1",
+        "move 1 from 1 to 2",
+    ];
+
+    fn example_stacks() -> Stacks {
+        Stacks::from([
+            (1, vec!['Z', 'N']),
+            (2, vec!['M', 'C', 'D']),
+            (3, vec!['P']),
+        ])
+    }
+
+    fn run(model: &str) -> (Crane, Vec<Stacks>) {
+        let mut crane = Crane::new(policy_for_model(model).unwrap(), example_stacks());
+        let mut states = vec![crane.stacks().clone()];
+        for (idx, line) in MOVES.iter().enumerate() {
+            crane.execute(idx + 1, line.parse().unwrap()).unwrap();
+            states.push(crane.stacks().clone());
+        }
+        (crane, states)
+    }
+
+    #[test]
+    fn readme_results() {
+        assert_eq!(run("9000").0.top_crates(), "CMZ");
+        assert_eq!(run("9001").0.top_crates(), "MCD");
+    }
+
+    #[test]
+    fn seeks_to_every_move() {
+        for model in ["9000", "9001"] {
+            let (mut crane, states) = run(model);
+            for k in [0, 3, 1, 4, 2, 2, 0] {
+                crane.seek(k).unwrap();
+                assert_eq!(crane.stacks(), &states[k], "{} after {} moves", model, k);
+                assert_eq!(crane.position(), k);
+            }
+            assert!(crane.seek(5).is_err());
+        }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let (mut crane, states) = run("9000");
+        assert!(!crane.step_forward());
+        for k in (0..MOVES.len()).rev() {
+            assert!(crane.step_back());
+            assert_eq!(crane.stacks(), &states[k]);
+        }
+        assert!(!crane.step_back());
+        assert!(crane.step_forward());
+        assert_eq!(crane.stacks(), &states[1]);
+
+        // a new move drops the undone ones
+        crane
+            .execute(9, "move 1 from 3 to 2".parse().unwrap())
+            .unwrap();
+        assert_eq!(crane.history().len(), 2);
+        assert!(!crane.step_forward());
+        assert!(crane.step_back());
+        assert_eq!(crane.stacks(), &states[1]);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::crane::Stacks;
+
+// A stack label read from the last line of the diagram, `start..end` are the character
+// positions it occupies.
+struct Label {
+    id: usize,
+    start: usize,
+    end: usize,
+}
+
+fn read_labels(line: &str, line_number: usize) -> Result<Vec<Label>, String> {
+    let mut labels: Vec<Label> = vec![];
+    let chars: Vec<char> = line.chars().collect();
+    let mut pos = 0;
+    while pos < chars.len() {
+        if chars[pos].is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while pos < chars.len() && !chars[pos].is_whitespace() {
+            pos += 1;
+        }
+        let text: String = chars[start..pos].iter().collect();
+        let id = text.parse::<usize>().map_err(|_| {
+            format!(
+                "Line {}, column {}: '{}' is not a stack label!",
+                line_number,
+                start + 1,
+                text
+            )
+        })?;
+        if labels.iter().any(|label| label.id == id) {
+            return Err(format!(
+                "Line {}, column {}: stack {} is labelled twice!",
+                line_number,
+                start + 1,
+                id
+            ));
+        }
+        labels.push(Label {
+            id,
+            start,
+            end: pos,
+        });
+    }
+    if labels.is_empty() {
+        return Err(format!("Line {}: no stack labels found!", line_number));
+    }
+    Ok(labels)
+}
+
+// Finds the label under the crate that spans the `start..end` characters of its row.
+// The label sharing the crate's middle column wins, otherwise the label has to overlap the crate.
+fn label_under(labels: &[Label], start: usize, end: usize) -> Option<&Label> {
+    let middle = start + (end - start) / 2;
+    labels
+        .iter()
+        .find(|label| label.start <= middle && middle < label.end)
+        .or_else(|| {
+            let mut overlapping = labels
+                .iter()
+                .filter(|label| label.start < end && start < label.end);
+            match (overlapping.next(), overlapping.next()) {
+                (Some(label), None) => Some(label),
+                _ => None,
+            }
+        })
+}
+
+// Parses the stack drawing. The label line is read first and every crate is assigned to
+// the label it stands above, so multi-digit labels, irregular spacing and trimmed
+// trailing spaces are all fine. `first_line` is the line number of the first row, it's
+// used for error messages only.
+pub fn parse_diagram(lines: &[&str], first_line: usize) -> Result<Stacks, String> {
+    let label_idx = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .ok_or_else(|| format!("Line {}: the stack diagram is empty!", first_line))?;
+    let labels = read_labels(lines[label_idx], first_line + label_idx)?;
+
+    let mut stacks: Stacks = labels.iter().map(|label| (label.id, vec![])).collect();
+    // stacks that already had a gap, nothing can lie above it
+    let mut topped = vec![false; labels.len()];
+    for (idx, line) in lines[..label_idx].iter().enumerate().rev() {
+        let line_number = first_line + idx;
+        let chars: Vec<char> = line.chars().collect();
+        let mut occupied = vec![false; labels.len()];
+        let mut pos = 0;
+        while pos < chars.len() {
+            match chars[pos] {
+                c if c.is_whitespace() => pos += 1,
+                '[' if chars.get(pos + 2) == Some(&']') && !chars[pos + 1].is_whitespace() => {
+                    let label = label_under(&labels, pos, pos + 3).ok_or_else(|| {
+                        format!(
+                            "Line {}, column {}: crate {} doesn't stand above a single stack label!",
+                            line_number,
+                            pos + 1,
+                            chars[pos + 1]
+                        )
+                    })?;
+                    let label_idx = labels.iter().position(|l| l.id == label.id).unwrap();
+                    if occupied[label_idx] {
+                        return Err(format!(
+                            "Line {}, column {}: two crates stand above stack {}!",
+                            line_number,
+                            pos + 1,
+                            label.id
+                        ));
+                    }
+                    if topped[label_idx] {
+                        return Err(format!(
+                            "Line {}, column {}: crate {} is floating above stack {}!",
+                            line_number,
+                            pos + 1,
+                            chars[pos + 1],
+                            label.id
+                        ));
+                    }
+                    occupied[label_idx] = true;
+                    stacks.get_mut(&label.id).unwrap().push(chars[pos + 1]);
+                    pos += 3;
+                }
+                c => {
+                    return Err(format!(
+                        "Line {}, column {}: unexpected '{}' in the stack diagram!",
+                        line_number,
+                        pos + 1,
+                        c
+                    ))
+                }
+            }
+        }
+        for (topped, occupied) in topped.iter_mut().zip(occupied) {
+            *topped |= !occupied;
+        }
+    }
+
+    Ok(stacks)
+}
+
+// Draws the stacks in the same format as the puzzle input, trailing spaces are trimmed.
+pub fn render_diagram(stacks: &Stacks) -> String {
+    let labels: Vec<String> = stacks.keys().map(|id| id.to_string()).collect();
+    let widths: Vec<usize> = labels.iter().map(|label| label.len().max(3)).collect();
+    let height = stacks.values().map(Vec::len).max().unwrap_or(0);
+
+    let mut rows = vec![];
+    for level in (0..height).rev() {
+        let cells = stacks
+            .values()
+            .zip(&widths)
+            .map(|(stack, &width)| match stack.get(level) {
+                Some(c) => format!("{:<width$}", format!("[{}]", c), width = width),
+                None => " ".repeat(width),
+            });
+        rows.push(cells.collect::<Vec<_>>().join(" ").trim_end().to_owned());
+    }
+    let label_cells = labels.iter().zip(&widths).map(|(label, &width)| {
+        // short labels are put under the crate's letter, longer ones start at the bracket
+        let label = if label.len() < 3 {
+            format!(" {}", label)
+        } else {
+            label.clone()
+        };
+        format!("{:<width$}", label, width = width)
+    });
+    rows.push(
+        label_cells
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end()
+            .to_owned(),
+    );
+
+    rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(stacks: &Stacks) {
+        let rendered = render_diagram(stacks);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(&parse_diagram(&lines, 1).unwrap(), stacks, "\n{}", rendered);
+    }
+
+    #[test]
+    fn readme_diagram_round_trips() {
+        let lines = ["    [D]    ", "[N] [C]    ", "[Z] [M] [P]", "1   2   3"];
+        let stacks = parse_diagram(&lines, 1).unwrap();
+        assert_eq!(
+            stacks,
+            Stacks::from([
+                (1, vec!['Z', 'N']),
+                (2, vec!['M', 'C', 'D']),
+                (3, vec!['P'])
+            ])
+        );
+        round_trip(&stacks);
+    }
+
+    #[test]
+    fn uneven_stacks_round_trip() {
+        round_trip(&Stacks::from([
+            (1, vec![]),
+            (2, vec!['A', 'B', 'C', 'D', 'E']),
+            (7, vec!['F']),
+            (10, vec![]),
+            (123, vec!['G', 'H']),
+        ]));
+    }
+}
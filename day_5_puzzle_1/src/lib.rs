@@ -0,0 +1,2 @@
+pub mod crane;
+pub mod diagram;
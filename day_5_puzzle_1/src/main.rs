@@ -1,63 +1,99 @@
-use regex::Regex;
-use std::collections::{HashMap, BTreeMap};
+use day_5_puzzle_1::crane::{policy_for_model, Crane, Instruction};
+use day_5_puzzle_1::diagram::{parse_diagram, render_diagram};
 use std::io::{prelude::*, BufReader};
-use std::{fs::File, io};
+use std::str::FromStr;
+use std::{fs::File, io, process};
 
+// Usage: day_5_puzzle_1 <input> [--model 9000|9001] [--after <k>] [--history] [--render]
+//                       [--verbose]
+// --verbose names the crane model, which --after does too.
 fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut model = "9000".to_owned();
+    let mut after = None;
+    let mut print_history = false;
+    let mut render = false;
+    let mut verbose = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => model = args.next().expect("No crane model given!"),
+            "--after" => {
+                after = Some(
+                    args.next()
+                        .and_then(|k| k.parse::<usize>().ok())
+                        .expect("--after expects a number of moves!"),
+                )
+            }
+            "--history" => print_history = true,
+            "--render" => render = true,
+            "--verbose" => verbose = true,
+            _ => panic!("Unknown argument {}!", arg),
+        }
+    }
+
     let file = File::open(input_path)?;
     let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-
-    let mut stacks = HashMap::<usize, Vec<char>>::new();
-    let mut ids = Vec::new();
-    let stack_re = Regex::new(r"(\[[A-Z]\]|[ ]{3})(?:[ ]?|$)").unwrap();
-    let id_re = Regex::new(r"\s*(\d)\s*").unwrap();
-    while let Some(Ok(line)) = lines.next() {
-        if line.is_empty() {
-            break;
-        }
+    let lines: Vec<String> = reader.lines().collect::<io::Result<_>>()?;
+    let diagram_end = lines
+        .iter()
+        .position(|line| line.is_empty())
+        .unwrap_or(lines.len());
 
-        for (i, cap) in stack_re.captures_iter(&line).enumerate() {
-            let elem = &cap[1];
-            let mut stack = stacks.entry(i).or_default();
-            if elem.starts_with("[") {
-                stack.push(elem.chars().nth(1).unwrap());
-            }
+    let diagram: Vec<&str> = lines[..diagram_end].iter().map(String::as_str).collect();
+    let stacks = parse_diagram(&diagram, 1).unwrap_or_else(|err| exit_with(&err));
+    let policy = policy_for_model(&model).unwrap_or_else(|err| exit_with(&err));
+    let mut crane = Crane::new(policy, stacks);
+
+    for (idx, line) in lines.iter().enumerate().skip(diagram_end + 1) {
+        if line.trim().is_empty() {
+            continue;
         }
+        let line_number = idx + 1;
+        let instruction = Instruction::from_str(line)
+            .unwrap_or_else(|err| exit_with(&format!("Line {}: {}", line_number, err)));
+        crane
+            .execute(line_number, instruction)
+            .unwrap_or_else(|err| exit_with(&err));
+    }
 
-        for id in id_re.captures_iter(&line) {
-            ids.push(id[1].parse::<usize>().unwrap());
+    if print_history {
+        for (k, record) in crane.history().iter().enumerate() {
+            println!(
+                "{:>5}. line {:>4}: move {} from {} to {} [{}]",
+                k + 1,
+                record.line,
+                record.instruction.count,
+                record.instruction.from,
+                record.instruction.to,
+                record.crates.iter().collect::<String>()
+            );
         }
     }
-    let mut ordered_stacks: BTreeMap<_, _> = stacks
-        .into_iter()
-        .map(|(key, stack)| {
-            let new_id = *ids.get(key).unwrap();
-            (new_id, stack)
-        })
-        .collect();
-
-    let re = Regex::new(r"move (\d*) from (\d*) to (\d*)").unwrap();
-    while let Some(Ok(line)) = lines.next() {
-        let captures = re.captures(&line).unwrap();
-        let n = captures.get(1).unwrap().as_str().parse::<usize>().unwrap();
-        let from = captures.get(2).unwrap().as_str().parse::<usize>().unwrap();
-        let to = captures.get(3).unwrap().as_str().parse::<usize>().unwrap();
-        let mut moved_stack = ordered_stacks.remove(&from).unwrap();
-        ordered_stacks.insert(from, moved_stack.split_off(n));
-        let to_stack = ordered_stacks.entry(to).or_default();
-        for element in moved_stack {
-            to_stack.insert(0, element);
+
+    if let Some(k) = after {
+        crane.seek(k).unwrap_or_else(|err| exit_with(&err));
+        println!("State after move {}:", k);
+        if !render {
+            for (id, stack) in crane.stacks() {
+                println!("{}: {}", id, stack.iter().collect::<String>());
+            }
         }
     }
 
-    let result: String = ordered_stacks
-        .into_iter()
-        .map(|(_, stack)| *stack.get(0).unwrap())
-        .collect();
+    if render {
+        println!("{}", render_diagram(crane.stacks()));
+    }
 
-    println!("Result: {}", result);
+    if verbose || after.is_some() {
+        println!("Crane: {}", crane.policy().name());
+    }
+    println!("Result: {}", crane.top_crates());
 
     Ok(())
 }
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
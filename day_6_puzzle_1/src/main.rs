@@ -1,26 +1,78 @@
-use std::collections::HashSet;
-use std::io::{prelude::*, BufReader};
+use day_6_puzzle_1::marker::{first_marker, Markers};
+use day_6_puzzle_1::protocol::decode;
+use std::io::BufReader;
 use std::{fs::File, io};
 
+// Usage: day_6_puzzle_1 <input> [--window <k>] [--all] [--decode [--json]]
 fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut window = 4;
+    let mut all = false;
+    let mut decode_frames = false;
+    let mut json = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => {
+                window = args
+                    .next()
+                    .and_then(|k| k.parse::<usize>().ok())
+                    .filter(|&k| k > 0)
+                    .expect("--window expects a positive number!")
+            }
+            "--all" => all = true,
+            "--decode" => decode_frames = true,
+            "--json" => json = true,
+            _ => panic!("Unknown argument {}!", arg),
+        }
+    }
     let file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
-    let mut message = String::new();
-    reader
-        .read_line(&mut message)
-        .expect("Reading a message has failed!");
+    let reader = BufReader::new(file);
 
-    let mut start_of_packet_idx = 0;
-    for idx in 0..message.len() - 4 {
-        let set: HashSet<_> = message[idx..idx + 4].chars().collect();
-        if set.len() == 4 {
-            start_of_packet_idx = idx + 4;
-            break;
+    if decode_frames {
+        let decoded = decode(reader)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&decoded)?);
+            return Ok(());
         }
+        for frame in &decoded.frames {
+            println!(
+                "#{:<5} {:<8} marker {} at {:>8}, payload {:>8}..{:<8} ({} bytes)",
+                frame.index,
+                format!("{:?}", frame.kind),
+                frame.marker,
+                frame.marker_start,
+                frame.payload_start,
+                frame.payload_end,
+                frame.payload_len()
+            );
+        }
+        println!(
+            "Decoded {} packets ({} payload bytes) and {} messages ({} payload bytes) out of {} bytes, preamble {} bytes",
+            decoded.summary.packets,
+            decoded.summary.packet_payload_bytes,
+            decoded.summary.messages,
+            decoded.summary.message_payload_bytes,
+            decoded.summary.total_bytes,
+            decoded.summary.preamble_bytes
+        );
+        return Ok(());
     }
 
-    println!("Marker can after {}th char!", start_of_packet_idx);
+    if all {
+        let mut count = 0;
+        for offset in Markers::new(reader, window) {
+            println!("{}", offset?);
+            count += 1;
+        }
+        println!("Found {} markers of {} distinct chars!", count, window);
+        return Ok(());
+    }
+
+    match first_marker(reader, window)? {
+        Some(offset) => println!("Marker can after {}th char!", offset),
+        None => println!("There is no marker of {} distinct chars!", window),
+    }
 
     Ok(())
 }
@@ -0,0 +1,183 @@
+use std::io::{self, Read};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Finds windows of `window` distinct symbols in a stream of bytes. Keeps a counter of
+// every symbol inside the window, so each byte is handled in constant time.
+// A line break clears the window, a marker can't span over it.
+pub struct MarkerDetector {
+    window: usize,
+    ring: Vec<u8>,
+    counts: [usize; 256],
+    distinct: usize,
+    filled: usize,
+    offset: u64,
+}
+
+impl MarkerDetector {
+    pub fn new(window: usize) -> Self {
+        Self::starting_at(window, 0)
+    }
+
+    // Detector for a stream of which `offset` bytes were already consumed.
+    pub fn starting_at(window: usize, offset: u64) -> Self {
+        assert!(window > 0, "The marker window can't be empty!");
+        Self {
+            window,
+            ring: vec![0; window],
+            counts: [0; 256],
+            distinct: 0,
+            filled: 0,
+            offset,
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    // Number of bytes consumed so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    // Consumes a single byte, returns the offset just after the marker if the last
+    // `window` bytes are all different.
+    pub fn push(&mut self, byte: u8) -> Option<u64> {
+        let slot = (self.offset % self.window as u64) as usize;
+        self.offset += 1;
+        if byte == b'\n' || byte == b'\r' {
+            self.clear();
+            return None;
+        }
+
+        if self.filled == self.window {
+            let old = self.ring[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
+        } else {
+            self.filled += 1;
+        }
+        self.ring[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
+        }
+
+        if self.filled == self.window && self.distinct == self.window {
+            return Some(self.offset);
+        }
+        None
+    }
+
+    // Bytes currently in the window, the oldest first.
+    pub fn window_bytes(&self) -> Vec<u8> {
+        let start = (self.offset % self.window as u64) as usize;
+        let mut bytes: Vec<u8> = self.ring[start..]
+            .iter()
+            .chain(&self.ring[..start])
+            .copied()
+            .collect();
+        bytes.drain(..self.window - self.filled);
+        bytes
+    }
+
+    fn clear(&mut self) {
+        self.counts = [0; 256];
+        self.distinct = 0;
+        self.filled = 0;
+    }
+}
+
+// Reads a stream in fixed size chunks and hands it out byte by byte.
+pub(crate) struct ChunkedBytes<R: Read> {
+    reader: R,
+    buffer: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    finished: bool,
+}
+
+impl<R: Read> ChunkedBytes<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for ChunkedBytes<R> {
+    type Item = io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            if self.pos < self.len {
+                self.pos += 1;
+                return Some(Ok(self.buffer[self.pos - 1]));
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => self.finished = true,
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+// Iterator over the end offsets of all the markers found in a stream, the stream is
+// read in chunks so it never has to fit in memory.
+pub struct Markers<R: Read> {
+    bytes: ChunkedBytes<R>,
+    detector: MarkerDetector,
+}
+
+impl<R: Read> Markers<R> {
+    pub fn new(reader: R, window: usize) -> Self {
+        Self {
+            bytes: ChunkedBytes::new(reader),
+            detector: MarkerDetector::new(window),
+        }
+    }
+
+    // Number of bytes read from the stream so far.
+    pub fn offset(&self) -> u64 {
+        self.detector.offset()
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.bytes.by_ref() {
+            match byte {
+                Ok(byte) => {
+                    if let Some(offset) = self.detector.push(byte) {
+                        return Some(Ok(offset));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
+
+// Offset just after the first marker in the stream, none if there is no such window.
+pub fn first_marker<R: Read>(reader: R, window: usize) -> io::Result<Option<u64>> {
+    Markers::new(reader, window).next().transpose()
+}
@@ -0,0 +1,188 @@
+use crate::marker::{ChunkedBytes, MarkerDetector};
+use serde::Serialize;
+use std::io::{self, Read};
+
+pub const PACKET_WINDOW: usize = 4;
+pub const MESSAGE_WINDOW: usize = 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameKind {
+    Packet,
+    Message,
+}
+
+impl FrameKind {
+    fn other(self) -> Self {
+        match self {
+            Self::Packet => Self::Message,
+            Self::Message => Self::Packet,
+        }
+    }
+}
+
+// A decoded frame, all the offsets are byte offsets in the datastream.
+// The marker occupies `marker_start..payload_start` and the payload
+// `payload_start..payload_end`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Frame {
+    pub index: usize,
+    pub kind: FrameKind,
+    pub marker: String,
+    pub marker_start: u64,
+    pub payload_start: u64,
+    pub payload_end: u64,
+}
+
+impl Frame {
+    pub fn payload_len(&self) -> u64 {
+        self.payload_end - self.payload_start
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Summary {
+    pub total_bytes: u64,
+    // bytes before the first start-of-packet marker
+    pub preamble_bytes: u64,
+    pub packets: usize,
+    pub messages: usize,
+    pub packet_payload_bytes: u64,
+    pub message_payload_bytes: u64,
+    pub longest_packet_payload: u64,
+    pub longest_message_payload: u64,
+}
+
+impl Summary {
+    fn record(&mut self, frame: &Frame) {
+        let (count, bytes, longest) = match frame.kind {
+            FrameKind::Packet => (
+                &mut self.packets,
+                &mut self.packet_payload_bytes,
+                &mut self.longest_packet_payload,
+            ),
+            FrameKind::Message => (
+                &mut self.messages,
+                &mut self.message_payload_bytes,
+                &mut self.longest_message_payload,
+            ),
+        };
+        *count += 1;
+        *bytes += frame.payload_len();
+        *longest = (*longest).max(frame.payload_len());
+    }
+}
+
+// Splits a datastream into alternating packets and messages. The stream opens with a
+// start-of-packet marker, the packet's payload lasts until the next start-of-message
+// marker, whose payload in turn lasts until the next start-of-packet marker and so on.
+// A marker is only looked for in the bytes that follow the previous one.
+pub struct Decoder<R: Read> {
+    bytes: ChunkedBytes<R>,
+    packet_window: usize,
+    message_window: usize,
+    detector: MarkerDetector,
+    looking_for: FrameKind,
+    open_frame: Option<Frame>,
+    frames: usize,
+    summary: Summary,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_windows(reader, PACKET_WINDOW, MESSAGE_WINDOW)
+    }
+
+    pub fn with_windows(reader: R, packet_window: usize, message_window: usize) -> Self {
+        Self {
+            bytes: ChunkedBytes::new(reader),
+            packet_window,
+            message_window,
+            detector: MarkerDetector::new(packet_window),
+            looking_for: FrameKind::Packet,
+            open_frame: None,
+            frames: 0,
+            summary: Summary::default(),
+        }
+    }
+
+    // Statistics of the frames decoded so far, complete once the iterator is exhausted.
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
+    fn window(&self, kind: FrameKind) -> usize {
+        match kind {
+            FrameKind::Packet => self.packet_window,
+            FrameKind::Message => self.message_window,
+        }
+    }
+
+    // Opens a new frame for the marker that was just found and returns the one it closes.
+    fn start_frame(&mut self, payload_start: u64) -> Option<Frame> {
+        let marker_start = payload_start - self.detector.window() as u64;
+        let frame = Frame {
+            index: self.frames,
+            kind: self.looking_for,
+            marker: String::from_utf8_lossy(&self.detector.window_bytes()).into_owned(),
+            marker_start,
+            payload_start,
+            payload_end: payload_start,
+        };
+        if self.frames == 0 {
+            self.summary.preamble_bytes = marker_start;
+        }
+        self.frames += 1;
+        self.looking_for = self.looking_for.other();
+        self.detector = MarkerDetector::starting_at(self.window(self.looking_for), payload_start);
+
+        let mut closed = self.open_frame.replace(frame)?;
+        closed.payload_end = marker_start;
+        self.summary.record(&closed);
+        Some(closed)
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(byte) = self.bytes.next() {
+            match byte {
+                Ok(byte) => {
+                    if let Some(offset) = self.detector.push(byte) {
+                        if let Some(frame) = self.start_frame(offset) {
+                            return Some(Ok(frame));
+                        }
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        self.summary.total_bytes = self.detector.offset();
+        if self.frames == 0 {
+            self.summary.preamble_bytes = self.summary.total_bytes;
+        }
+        let mut last = self.open_frame.take()?;
+        last.payload_end = self.summary.total_bytes;
+        self.summary.record(&last);
+        Some(Ok(last))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Decoded {
+    pub frames: Vec<Frame>,
+    pub summary: Summary,
+}
+
+// Decodes the whole stream, keeps only the frame boundaries in memory.
+pub fn decode<R: Read>(reader: R) -> io::Result<Decoded> {
+    let mut decoder = Decoder::new(reader);
+    let frames = decoder.by_ref().collect::<io::Result<Vec<Frame>>>()?;
+    Ok(Decoded {
+        frames,
+        summary: decoder.summary,
+    })
+}
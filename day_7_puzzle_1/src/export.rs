@@ -0,0 +1,176 @@
+use crate::filesystem::{FileSystem, FileType, NodeId};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+// Plain nested description of a file system, this is what gets written to JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Tree {
+    Dir { name: String, files: Vec<Tree> },
+    File { name: String, size: usize },
+}
+
+impl Tree {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Dir { name, .. } | Self::File { name, .. } => name,
+        }
+    }
+}
+
+pub fn to_tree(file_system: &FileSystem, from: NodeId) -> Tree {
+    let node = file_system.node(from);
+    match node.file_type {
+        FileType::Dir { ref files } => Tree::Dir {
+            name: node.name.clone(),
+            files: files.iter().map(|&id| to_tree(file_system, id)).collect(),
+        },
+        FileType::File { size } => Tree::File {
+            name: node.name.clone(),
+            size,
+        },
+    }
+}
+
+// A name has to be usable in a transcript line, so it can't contain any whitespace.
+// Names that can't leave the directory they're in.
+fn check_path_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return Err(format!("'{}' is not a valid file name!", name));
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    check_path_name(name)?;
+    if name.contains(char::is_whitespace) {
+        return Err(format!("'{}' can't be written to a transcript!", name));
+    }
+    Ok(())
+}
+
+fn add_tree(file_system: &mut FileSystem, dir: NodeId, files: &[Tree]) -> Result<(), String> {
+    for file in files {
+        check_name(file.name())?;
+        if file_system.child(dir, file.name()).is_some() {
+            return Err(format!(
+                "{} contains {} twice!",
+                file_system.path(dir),
+                file.name()
+            ));
+        }
+        file_system.set_current_dir(dir);
+        match file {
+            Tree::Dir { name, files } => {
+                let sub_dir = file_system.add_dir(name.clone());
+                add_tree(file_system, sub_dir, files)?;
+            }
+            Tree::File { name, size } => {
+                file_system.add_file(name.clone(), *size);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Builds a file system out of a tree, the tree's root becomes `/`.
+pub fn from_tree(tree: &Tree) -> Result<FileSystem, String> {
+    let Tree::Dir { files, .. } = tree else {
+        return Err("The root has to be a directory!".to_owned());
+    };
+    let mut file_system = FileSystem::new();
+    add_tree(&mut file_system, FileSystem::ROOT, files)?;
+    file_system.set_current_dir(FileSystem::ROOT);
+    Ok(file_system)
+}
+
+pub fn to_json(file_system: &FileSystem) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&to_tree(file_system, FileSystem::ROOT))
+}
+
+pub fn from_json(json: &str) -> Result<FileSystem, String> {
+    let tree: Tree = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    from_tree(&tree)
+}
+
+// Writes a `$ cd` / `$ ls` session that visits every directory, the way the puzzle input does.
+pub fn to_transcript(file_system: &FileSystem) -> Result<Vec<String>, String> {
+    fn visit(file_system: &FileSystem, dir: NodeId, lines: &mut Vec<String>) -> Result<(), String> {
+        lines.push("$ ls".to_owned());
+        for &id in file_system.files(dir) {
+            let node = file_system.node(id);
+            check_name(&node.name)?;
+            lines.push(match node.file_type {
+                FileType::Dir { .. } => format!("dir {}", node.name),
+                FileType::File { size } => format!("{} {}", size, node.name),
+            });
+        }
+        for &id in file_system.files(dir) {
+            let node = file_system.node(id);
+            if node.is_dir() {
+                lines.push(format!("$ cd {}", node.name));
+                visit(file_system, id, lines)?;
+                lines.push("$ cd ..".to_owned());
+            }
+        }
+        Ok(())
+    }
+
+    let mut lines = vec!["$ cd /".to_owned()];
+    visit(file_system, FileSystem::ROOT, &mut lines)?;
+    Ok(lines)
+}
+
+// Recreates the tree below `target` with sparse files of the right sizes.
+pub fn materialize(file_system: &FileSystem, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for &id in file_system.files(FileSystem::ROOT) {
+        materialize_node(file_system, id, target)?;
+    }
+    Ok(())
+}
+
+fn materialize_node(file_system: &FileSystem, id: NodeId, parent: &Path) -> io::Result<()> {
+    let node = file_system.node(id);
+    check_path_name(&node.name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let path = parent.join(&node.name);
+    match node.file_type {
+        FileType::Dir { ref files } => {
+            fs::create_dir(&path)?;
+            for &file in files {
+                materialize_node(file_system, file, &path)?;
+            }
+        }
+        FileType::File { size } => File::create(&path)?.set_len(size as u64)?,
+    }
+    Ok(())
+}
+
+// Reads a real directory into a tree, entries are sorted by name and anything that
+// is neither a regular file nor a directory is skipped.
+pub fn scan_dir(path: &Path) -> io::Result<Tree> {
+    let name = path.file_name().map_or_else(
+        || "/".to_owned(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_file() {
+        return Ok(Tree::File {
+            name,
+            size: metadata.len() as usize,
+        });
+    }
+
+    let mut entries = fs::read_dir(path)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    let mut files = vec![];
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() || file_type.is_file() {
+            files.push(scan_dir(&entry.path())?);
+        }
+    }
+    Ok(Tree::Dir { name, files })
+}
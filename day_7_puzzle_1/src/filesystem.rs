@@ -0,0 +1,191 @@
+// Index of a node in the file system's arena.
+pub type NodeId = usize;
+
+pub enum FileType {
+    Dir { files: Vec<NodeId> },
+    File { size: usize },
+}
+
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub file_type: FileType,
+    // total size of the node, kept up to date for directories whenever a file is added
+    size: usize,
+}
+
+impl Node {
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self.file_type, FileType::Dir { .. })
+    }
+}
+
+// Directory tree stored in a flat vector, nodes refer to each other by their indices.
+pub struct FileSystem {
+    nodes: Vec<Node>,
+    current_dir: NodeId,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: "/".to_owned(),
+                parent: None,
+                file_type: FileType::Dir { files: vec![] },
+                size: 0,
+            }],
+            current_dir: Self::ROOT,
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn size(&self, id: NodeId) -> usize {
+        self.nodes[id].size
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    pub fn current_dir(&self) -> NodeId {
+        self.current_dir
+    }
+
+    // Files and directories directly contained by the node, empty for plain files.
+    pub fn files(&self, id: NodeId) -> &[NodeId] {
+        match self.nodes[id].file_type {
+            FileType::Dir { ref files } => files,
+            FileType::File { .. } => &[],
+        }
+    }
+
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.files(dir)
+            .iter()
+            .copied()
+            .find(|&id| self.nodes[id].name == name)
+    }
+
+    // Absolute path of the node, e.g. `/a/e/i`.
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut node = id;
+        while let Some(parent) = self.nodes[node].parent {
+            names.push(self.nodes[node].name.as_str());
+            node = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Finds a node by its absolute path.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(Self::ROOT, |dir, name| self.child(dir, name))
+    }
+
+    fn add_node(&mut self, name: String, file_type: FileType, size: usize) -> NodeId {
+        let parent = self.current_dir;
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name,
+            parent: Some(parent),
+            file_type,
+            size,
+        });
+        if let FileType::Dir { ref mut files } = self.nodes[parent].file_type {
+            files.push(id);
+        }
+        let mut ancestor = Some(parent);
+        while let Some(dir) = ancestor {
+            self.nodes[dir].size += size;
+            ancestor = self.nodes[dir].parent;
+        }
+        id
+    }
+
+    pub fn add_dir(&mut self, name: String) -> NodeId {
+        self.add_node(name, FileType::Dir { files: vec![] }, 0)
+    }
+
+    pub fn add_file(&mut self, name: String, size: usize) -> NodeId {
+        self.add_node(name, FileType::File { size }, size)
+    }
+
+    pub fn set_current_dir(&mut self, dir: NodeId) {
+        assert!(
+            self.nodes[dir].is_dir(),
+            "{} is not a directory!",
+            self.path(dir)
+        );
+        self.current_dir = dir;
+    }
+
+    // Pre-order walk over the tree below `from` (inclusive), yields nodes with their depth.
+    pub fn walk(&self, from: NodeId) -> Walk<'_> {
+        Walk {
+            file_system: self,
+            stack: vec![(from, 0)],
+        }
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.walk(Self::ROOT)
+            .map(|(id, _)| id)
+            .filter(|&id| self.nodes[id].is_dir())
+    }
+}
+
+pub struct Walk<'a> {
+    file_system: &'a FileSystem,
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = (NodeId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, depth) = self.stack.pop()?;
+        self.stack.extend(
+            self.file_system
+                .files(id)
+                .iter()
+                .rev()
+                .map(|&file| (file, depth + 1)),
+        );
+        Some((id, depth))
+    }
+}
+
+// Finds directories that have overall size of `limit` and less.
+pub fn find_dirs_to_remove(file_system: &FileSystem, limit: usize) -> Vec<NodeId> {
+    file_system
+        .dirs()
+        .filter(|&id| file_system.size(id) <= limit)
+        .collect()
+}
+
+// Finds the smallest directory that frees up at least `missing_space` when removed.
+pub fn find_dir_to_remove(file_system: &FileSystem, missing_space: usize) -> Option<NodeId> {
+    file_system
+        .dirs()
+        .filter(|&id| file_system.size(id) >= missing_space)
+        .min_by_key(|&id| file_system.size(id))
+}
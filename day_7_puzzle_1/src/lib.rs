@@ -0,0 +1,5 @@
+pub mod export;
+pub mod filesystem;
+pub mod planner;
+pub mod query;
+pub mod transcript;
@@ -1,239 +1,244 @@
-use std::cell::RefCell;
+use day_7_puzzle_1::export::{
+    from_json, from_tree, materialize, scan_dir, to_json, to_transcript, to_tree,
+};
+use day_7_puzzle_1::filesystem::{find_dirs_to_remove, FileSystem, NodeId};
+use day_7_puzzle_1::planner::{plan_cleanup, Objective, PlanRequest};
+use day_7_puzzle_1::query::{disk_usage, find, largest, render_tree, FindQuery, Kind};
+use day_7_puzzle_1::transcript::{interpret, ImplicitDirs};
 use std::io::{BufRead, BufReader};
-use std::ops::{Deref, DerefMut};
-use std::rc::{Rc, Weak};
+use std::path::Path;
 use std::str::FromStr;
-use std::{fs::File, io};
-
-enum CdType {
-    Into { name: String },
-    Up,
-    Root,
-}
+use std::{fs, fs::File, io, process};
+
+// Usage: day_7_puzzle_1 <input> [--from transcript|json|dir] [--implicit-dirs create|flag]
+//                       [--strict] [<command>]
+// Commands:
+//   tree [<path>]
+//   du [-d <depth>] [<path>]
+//   find [<path>] [-name <glob>] [-type f|d] [-minsize <n>] [-maxsize <n>]
+//        [-mindepth <n>] [-maxdepth <n>]
+//   top [-n <k>]
+//   json
+//   transcript
+//   materialize [<dir>]   (a new temporary directory if none is given)
+//   plan [--disk <size>] [--needed <size>] [--objective fewest|overshoot]
+//        [--protect <path>]...
+//   roundtrip             (checks that the generated transcript rebuilds the same tree)
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut implicit_dirs = ImplicitDirs::default();
+    let mut strict = false;
+    let mut source = "transcript".to_owned();
+    let mut command = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--implicit-dirs" => {
+                implicit_dirs = ImplicitDirs::from_str(
+                    &args.next().expect("No implicit directories mode given!"),
+                )
+                .unwrap()
+            }
+            "--strict" => strict = true,
+            "--from" => source = args.next().expect("No input format given!"),
+            _ => {
+                command.push(arg);
+                command.extend(args.by_ref());
+            }
+        }
+    }
+    let file_system = match source.as_str() {
+        "transcript" => {
+            let file = File::open(input_path)?;
+            let reader = BufReader::new(file);
+            let lines = reader.lines().collect::<io::Result<Vec<_>>>()?;
+
+            let interpretation = interpret(&lines, implicit_dirs);
+            for diagnostic in &interpretation.diagnostics {
+                eprintln!("{}", diagnostic);
+            }
+            if strict && interpretation.has_errors() {
+                process::exit(1);
+            }
+            interpretation.file_system
+        }
+        "json" => from_json(&fs::read_to_string(input_path)?).unwrap_or_else(|err| exit_with(&err)),
+        "dir" => {
+            from_tree(&scan_dir(Path::new(&input_path))?).unwrap_or_else(|err| exit_with(&err))
+        }
+        _ => exit_with(&format!("Unknown input format {}!", source)),
+    };
 
-enum Command {
-    CD { cd_type: CdType },
-    LS,
-}
+    if !command.is_empty() {
+        run_command(&file_system, &command).unwrap_or_else(|err| exit_with(&err));
+        return Ok(());
+    }
 
-impl FromStr for Command {
-    type Err = String;
+    let freed_up_space: usize = find_dirs_to_remove(&file_system, 100000)
+        .into_iter()
+        .map(|dir| file_system.size(dir))
+        .sum();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(" ");
-        if Some("$") != parts.next() {
-            return Err("It's not a command!".to_owned());
-        }
+    println!("Freed up space: {}", freed_up_space);
 
-        return match parts.next() {
-            Some("cd") => match parts.next() {
-                Some("/") => Ok(Command::CD {
-                    cd_type: CdType::Root,
-                }),
-                Some("..") => Ok(Command::CD {
-                    cd_type: CdType::Up,
-                }),
-                Some(name) => Ok(Command::CD {
-                    cd_type: CdType::Into {
-                        name: name.to_owned(),
-                    },
-                }),
-                None => Err("CD command is missing an argument!".to_owned()),
-            },
-            Some("ls") => Ok(Command::LS),
-            Some(command) => Err(format!("Couldn't recognize the command {}", command)),
-            None => Err("Empty command!".to_owned()),
-        };
-    }
+    Ok(())
 }
 
-enum FileType {
-    Dir {
-        parent: Weak<RefCell<FileType>>,
-        name: String,
-        files: Vec<Rc<RefCell<FileType>>>,
-    },
-    File {
-        size: usize,
-        name: String,
-    },
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
 
-impl FileType {
-    fn size(&self) -> usize {
-        match *self {
-            Self::File { size, name: _ } => size,
-            Self::Dir {
-                parent: _,
-                name: _,
-                ref files,
-            } => files.iter().map(|file| file.borrow().size()).sum(),
-        }
-    }
-
-    fn name(&self) -> &str {
-        match *self {
-            Self::File { size: _, ref name } => name,
-            Self::Dir {
-                parent: _,
-                ref name,
-                files: _,
-            } => name,
-        }
-    }
+fn number(value: Option<&String>, flag: &str) -> Result<usize, String> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("{} expects a number!", flag))
 }
 
-struct FileSystem {
-    root_dir: Rc<RefCell<FileType>>,
-    current_dir: Weak<RefCell<FileType>>,
+fn lookup(file_system: &FileSystem, path: &str) -> Result<NodeId, String> {
+    file_system
+        .lookup(path)
+        .ok_or_else(|| format!("There is no {}!", path))
 }
 
-impl FileSystem {
-    fn new() -> Self {
-        let root_dir = Rc::new(RefCell::new(FileType::Dir {
-            parent: Weak::new(),
-            name: "/".to_owned(),
-            files: vec![],
-        }));
-        Self {
-            root_dir: Rc::clone(&root_dir),
-            current_dir: Rc::downgrade(&root_dir),
-        }
-    }
-
-    fn add_dir(&mut self, name: String) {
-        let parent = self.current_dir.clone();
-        if let FileType::Dir {
-            parent: _,
-            name: _,
-            files,
-        } = self
-            .current_dir
-            .upgrade()
-            .expect("The current dir is broken! :(")
-            .borrow_mut()
-            .deref_mut()
-        {
-            files.push(Rc::new(RefCell::new(FileType::Dir {
-                parent,
-                name,
-                files: vec![],
-            })));
+fn run_command(file_system: &FileSystem, command: &[String]) -> Result<(), String> {
+    let mut args = command[1..].iter();
+    let mut path = "/".to_owned();
+    match command[0].as_str() {
+        "tree" => {
+            if let Some(arg) = args.next() {
+                path = arg.clone();
+            }
+            print!("{}", render_tree(file_system, lookup(file_system, &path)?));
         }
-    }
-
-    fn add_file(&mut self, name: String, size: usize) {
-        if let FileType::Dir {
-            parent: _,
-            name: _,
-            files,
-        } = self
-            .current_dir
-            .upgrade()
-            .expect("The current dir is broken! :(")
-            .borrow_mut()
-            .deref_mut()
-        {
-            files.push(Rc::new(RefCell::new(FileType::File { name, size })));
+        "du" => {
+            let mut depth = usize::MAX;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-d" => depth = number(args.next(), "-d")?,
+                    _ => path = arg.clone(),
+                }
+            }
+            for dir in disk_usage(file_system, lookup(file_system, &path)?, depth) {
+                println!("{}\t{}", file_system.size(dir), file_system.path(dir));
+            }
         }
-    }
-
-    fn change_directory(&mut self, cd_type: &CdType) {
-        if let FileType::Dir {
-            parent,
-            name: _,
-            files,
-        } = self
-            .current_dir
-            .upgrade()
-            .expect("The current dir is broken! :(")
-            .borrow()
-            .deref()
-        {
-            match cd_type {
-                CdType::Into { name } => {
-                    let file = files
-                        .iter()
-                        .find(|f| f.borrow().deref().name() == name)
-                        .expect("Couldn't find a file ");
-                    self.current_dir = Rc::downgrade(file);
+        "find" => {
+            let mut query = FindQuery::default();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-name" => {
+                        query.name = Some(args.next().ok_or("-name expects a pattern!")?.clone())
+                    }
+                    "-type" => {
+                        query.kind = match args.next().map(String::as_str) {
+                            Some("f") => Some(Kind::File),
+                            Some("d") => Some(Kind::Dir),
+                            _ => return Err("-type expects f or d!".to_owned()),
+                        }
+                    }
+                    "-minsize" => query.min_size = Some(number(args.next(), arg)?),
+                    "-maxsize" => query.max_size = Some(number(args.next(), arg)?),
+                    "-mindepth" => query.min_depth = Some(number(args.next(), arg)?),
+                    "-maxdepth" => query.max_depth = Some(number(args.next(), arg)?),
+                    _ if arg.starts_with('-') => {
+                        return Err(format!("Unknown find option {}!", arg))
+                    }
+                    _ => path = arg.clone(),
                 }
-                CdType::Root => {
-                    self.current_dir = Rc::downgrade(&self.root_dir);
+            }
+            for id in find(file_system, lookup(file_system, &path)?, &query) {
+                println!("{}\t{}", file_system.size(id), file_system.path(id));
+            }
+        }
+        "top" => {
+            let mut k = 10;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-n" => k = number(args.next(), "-n")?,
+                    _ => return Err(format!("Unknown top option {}!", arg)),
                 }
-                CdType::Up => {
-                    self.current_dir = parent.clone();
+            }
+            for (title, kind) in [
+                ("Largest files:", Kind::File),
+                ("Largest directories:", Kind::Dir),
+            ] {
+                println!("{}", title);
+                for id in largest(file_system, kind, k) {
+                    println!("{:>12}  {}", file_system.size(id), file_system.path(id));
                 }
             }
         }
-    }
-}
-
-// Finds directories that have overall size of 10000 and less.
-// Returns a vector of pointers to those directories.
-fn find_dirs_to_remove(dir: Rc<RefCell<FileType>>) -> Vec<Rc<RefCell<FileType>>> {
-    if let FileType::Dir {
-        parent: _,
-        name: _,
-        files,
-    } = dir.borrow().deref()
-    {
-        let mut result: Vec<Rc<RefCell<FileType>>> = vec![];
-
-        if dir.borrow().deref().size() <= 100000 {
-            result.push(Rc::clone(&dir));
+        "json" => println!("{}", to_json(file_system).map_err(|err| err.to_string())?),
+        "transcript" => {
+            for line in to_transcript(file_system)? {
+                println!("{}", line);
+            }
         }
-
-        for file in files {
-            result.append(&mut find_dirs_to_remove(Rc::clone(file)));
+        "materialize" => {
+            let target = match args.next() {
+                Some(dir) => Path::new(dir).to_path_buf(),
+                None => tempfile::Builder::new()
+                    .prefix("day_7_")
+                    .tempdir()
+                    .map(|dir| dir.keep())
+                    .map_err(|err| err.to_string())?,
+            };
+            materialize(file_system, &target).map_err(|err| err.to_string())?;
+            println!("Materialized into {}", target.display());
         }
-        return result;
-    }
-
-    vec![]
-}
-
-fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-
-    let mut file_system = FileSystem::new();
-    let mut read_output = false;
-    while let Some(Ok(ref line)) = lines.next() {
-        if line.starts_with("$") {
-            read_output = false;
-            let command = Command::from_str(line).unwrap();
-            match command {
-                Command::CD { ref cd_type } => file_system.change_directory(cd_type),
-                Command::LS {} => read_output = true,
-            }
-        } else if read_output {
-            let mut split = line.split(" ");
-            let dir_or_size = split.next();
-            match dir_or_size {
-                Some("dir") => {
-                    file_system.add_dir(split.next().expect("DIR does not have a name!").to_owned())
+        "plan" => {
+            let mut request = PlanRequest::default();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--disk" => request.disk_size = number(args.next(), arg)?,
+                    "--needed" => request.needed_free_space = number(args.next(), arg)?,
+                    "--objective" => {
+                        request.objective =
+                            Objective::from_str(args.next().ok_or("No objective given!")?)?
+                    }
+                    "--protect" => {
+                        let path = args.next().ok_or("No protected path given!")?;
+                        request.protected.push(lookup(file_system, path)?);
+                    }
+                    _ => return Err(format!("Unknown plan option {}!", arg)),
                 }
-                Some(size) => file_system.add_file(
-                    split.next().expect("FILE does not have a name!").to_owned(),
-                    size.parse().expect("Unknown size!"),
-                ),
-                None => panic!("Unknown output: {}!", line),
-            }
-        } else {
-            panic!("I don't know how to interpret this {}", line);
+            }
+            let plan = plan_cleanup(file_system, &request)?;
+            for &dir in &plan.dirs {
+                println!("{:>12}  {}", file_system.size(dir), file_system.path(dir));
+            }
+            println!(
+                "Removing {} directories frees {} bytes, free space {} -> {} (needed {}){}",
+                plan.dirs.len(),
+                plan.freed_space,
+                plan.free_space_before,
+                plan.free_space_after,
+                request.needed_free_space,
+                if plan.optimal {
+                    ""
+                } else {
+                    ", the search was cut short so the plan may not be the best one"
+                }
+            );
         }
+        "roundtrip" => {
+            let transcript = to_transcript(file_system)?;
+            let interpretation = interpret(&transcript, ImplicitDirs::Flag);
+            if let Some(diagnostic) = interpretation.diagnostics.first() {
+                return Err(format!("Generated transcript is invalid, {}", diagnostic));
+            }
+            let expected = to_tree(file_system, FileSystem::ROOT);
+            let rebuilt = to_tree(&interpretation.file_system, FileSystem::ROOT);
+            if rebuilt != expected {
+                return Err("Generated transcript rebuilds a different tree!".to_owned());
+            }
+            println!(
+                "Round trip of {} transcript lines is fine",
+                transcript.len()
+            );
+        }
+        _ => return Err(format!("Unknown command {}!", command[0])),
     }
-
-    let dirs_to_remove = find_dirs_to_remove(file_system.root_dir);
-    let freed_up_space: usize = dirs_to_remove
-        .iter()
-        .map(|file| file.borrow().deref().size())
-        .sum();
-
-    println!("Freed up space: {}", freed_up_space);
-
     Ok(())
 }
@@ -0,0 +1,241 @@
+use crate::filesystem::{FileSystem, NodeId};
+use std::str::FromStr;
+
+pub const DISK_SIZE: usize = 70000000;
+pub const NEEDED_FREE_SPACE: usize = 30000000;
+
+// Upper limit of the search steps, past it the best plan found so far is returned.
+const SEARCH_BUDGET: usize = 2_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    // remove as few directories as possible, ties are broken by the overshoot
+    FewestDirs,
+    // free as little space above the target as possible, ties are broken by the count
+    LeastOvershoot,
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fewest" => Ok(Self::FewestDirs),
+            "overshoot" => Ok(Self::LeastOvershoot),
+            _ => Err(format!("Unknown objective {}!", s)),
+        }
+    }
+}
+
+pub struct PlanRequest {
+    pub disk_size: usize,
+    pub needed_free_space: usize,
+    pub objective: Objective,
+    // neither these nor any directory containing them may be removed
+    pub protected: Vec<NodeId>,
+}
+
+impl Default for PlanRequest {
+    fn default() -> Self {
+        Self {
+            disk_size: DISK_SIZE,
+            needed_free_space: NEEDED_FREE_SPACE,
+            objective: Objective::FewestDirs,
+            protected: vec![],
+        }
+    }
+}
+
+pub struct Plan {
+    // none of the directories contains another one
+    pub dirs: Vec<NodeId>,
+    pub freed_space: usize,
+    pub free_space_before: usize,
+    pub free_space_after: usize,
+    // false if the search budget ran out before the plan was proven to be the best
+    pub optimal: bool,
+}
+
+struct Candidate {
+    id: NodeId,
+    size: usize,
+    // pre-order interval of the subtree, used to tell if one directory contains another
+    first: usize,
+    last: usize,
+}
+
+impl Candidate {
+    fn nested_with(&self, other: &Candidate) -> bool {
+        (self.first <= other.first && other.first <= self.last)
+            || (other.first <= self.first && self.first <= other.last)
+    }
+}
+
+struct Search<'a> {
+    candidates: &'a [Candidate],
+    // sum of the sizes of the candidates starting at the index, an optimistic bound
+    remaining: Vec<usize>,
+    missing: usize,
+    objective: Objective,
+    steps: usize,
+    chosen: Vec<usize>,
+    best: Option<(Vec<usize>, usize)>,
+}
+
+impl Search<'_> {
+    fn score(&self, count: usize, freed: usize) -> (usize, usize) {
+        let overshoot = freed - self.missing;
+        match self.objective {
+            Objective::FewestDirs => (count, overshoot),
+            Objective::LeastOvershoot => (overshoot, count),
+        }
+    }
+
+    fn is_better(&self, count: usize, freed: usize) -> bool {
+        match self.best {
+            Some((ref dirs, best_freed)) => {
+                self.score(count, freed) < self.score(dirs.len(), best_freed)
+            }
+            None => true,
+        }
+    }
+
+    // Most directories a plan may have to still beat the best one.
+    fn max_count(&self) -> usize {
+        match (self.objective, &self.best) {
+            (_, None) => usize::MAX,
+            (Objective::FewestDirs, Some((dirs, _))) => dirs.len(),
+            // nothing frees less than an exact hit, so only a smaller count could win
+            (Objective::LeastOvershoot, Some((dirs, freed))) if *freed == self.missing => {
+                dirs.len() - 1
+            }
+            (Objective::LeastOvershoot, Some(_)) => usize::MAX,
+        }
+    }
+
+    // Most space that `slots` more directories starting at the index could free.
+    fn bound(&self, idx: usize, slots: usize) -> usize {
+        let end = idx.saturating_add(slots).min(self.candidates.len());
+        self.remaining[idx] - self.remaining[end]
+    }
+
+    fn run(&mut self, start: usize, freed: usize) {
+        for idx in start..self.candidates.len() {
+            let slots = self.max_count().saturating_sub(self.chosen.len());
+            if self.steps >= SEARCH_BUDGET || freed + self.bound(idx, slots) < self.missing {
+                return;
+            }
+            self.steps += 1;
+            let candidate = &self.candidates[idx];
+            if self
+                .chosen
+                .iter()
+                .any(|&other| self.candidates[other].nested_with(candidate))
+            {
+                continue;
+            }
+            let count = self.chosen.len() + 1;
+            let total = freed + candidate.size;
+            if total >= self.missing {
+                if self.is_better(count, total) {
+                    self.chosen.push(idx);
+                    self.best = Some((self.chosen.clone(), total));
+                    self.chosen.pop();
+                }
+                continue;
+            }
+            if count >= self.max_count() {
+                continue;
+            }
+            self.chosen.push(idx);
+            self.run(idx + 1, total);
+            self.chosen.pop();
+        }
+    }
+}
+
+// Chooses directories whose removal frees at least the needed space.
+pub fn plan_cleanup(file_system: &FileSystem, request: &PlanRequest) -> Result<Plan, String> {
+    let used_space = file_system.size(FileSystem::ROOT);
+    let free_space_before = request.disk_size.checked_sub(used_space).ok_or_else(|| {
+        format!(
+            "{} bytes are used on a disk of {}!",
+            used_space, request.disk_size
+        )
+    })?;
+    let missing = request.needed_free_space.saturating_sub(free_space_before);
+    let mut plan = Plan {
+        dirs: vec![],
+        freed_space: 0,
+        free_space_before,
+        free_space_after: free_space_before,
+        optimal: true,
+    };
+    if missing == 0 {
+        return Ok(plan);
+    }
+
+    // pre-order positions, `last` is the position of the last node of the subtree
+    let order: Vec<NodeId> = file_system
+        .walk(FileSystem::ROOT)
+        .map(|(id, _)| id)
+        .collect();
+    let mut position = vec![0; order.len()];
+    for (pos, &id) in order.iter().enumerate() {
+        position[id] = pos;
+    }
+    let mut last = position.clone();
+    for &id in order.iter().rev() {
+        if let Some(parent) = file_system.parent(id) {
+            last[parent] = last[parent].max(last[id]);
+        }
+    }
+    let blocked = |id: NodeId| {
+        request
+            .protected
+            .iter()
+            .any(|&p| position[id] <= position[p] && position[p] <= last[id])
+            || request
+                .protected
+                .iter()
+                .any(|&p| position[p] <= position[id] && position[id] <= last[p])
+    };
+
+    let mut candidates: Vec<Candidate> = file_system
+        .dirs()
+        .filter(|&id| file_system.size(id) > 0 && !blocked(id))
+        .map(|id| Candidate {
+            id,
+            size: file_system.size(id),
+            first: position[id],
+            last: last[id],
+        })
+        .collect();
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.size));
+    let mut remaining = vec![0; candidates.len() + 1];
+    for idx in (0..candidates.len()).rev() {
+        remaining[idx] = remaining[idx + 1] + candidates[idx].size;
+    }
+
+    let mut search = Search {
+        candidates: &candidates,
+        remaining,
+        missing,
+        objective: request.objective,
+        steps: 0,
+        chosen: vec![],
+        best: None,
+    };
+    search.run(0, 0);
+    let (chosen, freed_space) = search.best.take().ok_or_else(|| {
+        format!(
+            "Removing unprotected directories can't free {} more bytes!",
+            missing
+        )
+    })?;
+    plan.dirs = chosen.into_iter().map(|idx| candidates[idx].id).collect();
+    plan.freed_space = freed_space;
+    plan.free_space_after = free_space_before + freed_space;
+    plan.optimal = search.steps < SEARCH_BUDGET;
+    Ok(plan)
+}
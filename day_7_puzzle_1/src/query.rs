@@ -0,0 +1,132 @@
+use crate::filesystem::{FileSystem, NodeId};
+use std::cmp::Reverse;
+
+// Draws the tree below `from` in the same way as the puzzle description does.
+pub fn render_tree(file_system: &FileSystem, from: NodeId) -> String {
+    let mut output = String::new();
+    for (id, depth) in file_system.walk(from) {
+        let node = file_system.node(id);
+        let kind = if node.is_dir() { "dir" } else { "file" };
+        output += &format!(
+            "{}- {} ({}, size={})\n",
+            "    ".repeat(depth),
+            node.name,
+            kind,
+            node.size()
+        );
+    }
+    output
+}
+
+// Directories below `from` that are at most `max_depth` levels deep, children are
+// listed before their parent like `du -d N` does.
+pub fn disk_usage(file_system: &FileSystem, from: NodeId, max_depth: usize) -> Vec<NodeId> {
+    fn visit(
+        file_system: &FileSystem,
+        dir: NodeId,
+        depth: usize,
+        max_depth: usize,
+        out: &mut Vec<NodeId>,
+    ) {
+        if depth < max_depth {
+            for &file in file_system.files(dir) {
+                if file_system.node(file).is_dir() {
+                    visit(file_system, file, depth + 1, max_depth, out);
+                }
+            }
+        }
+        out.push(dir);
+    }
+
+    let mut dirs = vec![];
+    visit(file_system, from, 0, max_depth, &mut dirs);
+    dirs
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    Dir,
+}
+
+// Criteria of the `find` command, all the given ones have to match.
+// The depth is counted from the directory the search starts in.
+#[derive(Clone, Debug, Default)]
+pub struct FindQuery {
+    pub name: Option<String>,
+    pub kind: Option<Kind>,
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+}
+
+impl FindQuery {
+    fn matches(&self, file_system: &FileSystem, id: NodeId, depth: usize) -> bool {
+        let node = file_system.node(id);
+        let kind = if node.is_dir() { Kind::Dir } else { Kind::File };
+        self.kind.is_none_or(|k| k == kind)
+            && self.min_size.is_none_or(|min| node.size() >= min)
+            && self.max_size.is_none_or(|max| node.size() <= max)
+            && self.min_depth.is_none_or(|min| depth >= min)
+            && self.max_depth.is_none_or(|max| depth <= max)
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|pattern| glob_match(pattern, &node.name))
+    }
+}
+
+pub fn find(file_system: &FileSystem, from: NodeId, query: &FindQuery) -> Vec<NodeId> {
+    file_system
+        .walk(from)
+        .filter(|&(_, depth)| query.max_depth.is_none_or(|max| depth <= max))
+        .filter(|&(id, depth)| query.matches(file_system, id, depth))
+        .map(|(id, _)| id)
+        .collect()
+}
+
+// Shell-like pattern match, `*` matches any sequence of characters and `?` a single one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last star and the name position it was tried with
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    p = star + 1;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// The `k` biggest nodes of the given kind, the biggest first.
+pub fn largest(file_system: &FileSystem, kind: Kind, k: usize) -> Vec<NodeId> {
+    let mut nodes = find(
+        file_system,
+        FileSystem::ROOT,
+        &FindQuery {
+            kind: Some(kind),
+            ..Default::default()
+        },
+    );
+    nodes.sort_by_key(|&id| (Reverse(file_system.size(id)), file_system.path(id)));
+    nodes.truncate(k);
+    nodes
+}
@@ -0,0 +1,379 @@
+use crate::filesystem::{FileSystem, FileType, NodeId};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+pub enum CdType {
+    Into { name: String },
+    Up,
+    Root,
+}
+
+pub enum Command {
+    CD { cd_type: CdType },
+    LS,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(' ');
+        if Some("$") != parts.next() {
+            return Err("It's not a command!".to_owned());
+        }
+
+        match parts.next() {
+            Some("cd") => match parts.next() {
+                Some("/") => Ok(Command::CD {
+                    cd_type: CdType::Root,
+                }),
+                Some("..") => Ok(Command::CD {
+                    cd_type: CdType::Up,
+                }),
+                Some(name) if !is_file_name(name) => Err(format!(
+                    "Can't cd into {}, it's not a directory name!",
+                    name
+                )),
+                Some(name) => Ok(Command::CD {
+                    cd_type: CdType::Into {
+                        name: name.to_owned(),
+                    },
+                }),
+                None => Err("CD command is missing an argument!".to_owned()),
+            },
+            Some("ls") => Ok(Command::LS),
+            Some(command) => Err(format!("Couldn't recognize the command {}", command)),
+            None => Err("Empty command!".to_owned()),
+        }
+    }
+}
+
+// What to do on `cd` into a directory that no listing has shown before.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImplicitDirs {
+    // create the directory and report a warning
+    #[default]
+    Create,
+    // report an error and ignore everything until the transcript gets back to a known directory
+    Flag,
+}
+
+impl FromStr for ImplicitDirs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(Self::Create),
+            "flag" => Ok(Self::Flag),
+            _ => Err(format!("Unknown implicit directories mode {}!", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    // `ls` run again in a directory that was already listed
+    RelistedDir {
+        path: String,
+    },
+    ImplicitDir {
+        path: String,
+    },
+    UnknownCdTarget {
+        path: String,
+    },
+    CdAboveRoot,
+    // the same file was listed with two different sizes, the first one is kept
+    ConflictingSize {
+        path: String,
+        kept: usize,
+        seen: usize,
+    },
+    // the same name was listed once as a file and once as a directory, the first one is kept
+    ConflictingType {
+        path: String,
+    },
+    UnknownCommand {
+        text: String,
+    },
+    UnrecognizedOutput {
+        text: String,
+    },
+    OutputWithoutLs {
+        text: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            DiagnosticKind::RelistedDir { .. } => Severity::Note,
+            DiagnosticKind::ImplicitDir { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "line {}: {}: ", self.line, severity)?;
+        match self.kind {
+            DiagnosticKind::RelistedDir { ref path } => {
+                write!(f, "directory {} is listed again", path)
+            }
+            DiagnosticKind::ImplicitDir { ref path } => {
+                write!(f, "directory {} was never listed, created it", path)
+            }
+            DiagnosticKind::UnknownCdTarget { ref path } => {
+                write!(f, "can't change directory to unknown {}", path)
+            }
+            DiagnosticKind::CdAboveRoot => write!(f, "can't go above the root directory"),
+            DiagnosticKind::ConflictingSize {
+                ref path,
+                kept,
+                seen,
+            } => write!(
+                f,
+                "file {} is listed with size {} but it was {} before",
+                path, seen, kept
+            ),
+            DiagnosticKind::ConflictingType { ref path } => {
+                write!(f, "{} is listed both as a file and as a directory", path)
+            }
+            DiagnosticKind::UnknownCommand { ref text } => {
+                write!(f, "unknown command '{}'", text)
+            }
+            DiagnosticKind::UnrecognizedOutput { ref text } => {
+                write!(f, "unrecognized output '{}'", text)
+            }
+            DiagnosticKind::OutputWithoutLs { ref text } => {
+                write!(f, "output '{}' doesn't follow an ls command", text)
+            }
+        }
+    }
+}
+
+pub struct Interpretation {
+    pub file_system: FileSystem,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Interpretation {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity() == Severity::Error)
+    }
+}
+
+enum Entry {
+    Dir,
+    File(usize),
+}
+
+// A name of a single file or directory, not a path or one which would point
+// elsewhere.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', ' '])
+}
+
+fn parse_entry(line: &str) -> Option<(Entry, &str)> {
+    let (first, name) = line.split_once(' ')?;
+    if !is_file_name(name) {
+        return None;
+    }
+    if first == "dir" {
+        return Some((Entry::Dir, name));
+    }
+    Some((Entry::File(first.parse().ok()?), name))
+}
+
+struct Interpreter {
+    options: ImplicitDirs,
+    file_system: FileSystem,
+    diagnostics: Vec<Diagnostic>,
+    listed_dirs: HashSet<NodeId>,
+    read_output: bool,
+    // how deep below a flagged unknown directory the transcript is, 0 if it's in a known one
+    lost_depth: usize,
+}
+
+impl Interpreter {
+    fn report(&mut self, line: usize, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic { line, kind });
+    }
+
+    fn child_path(&self, name: &str) -> String {
+        let dir = self.file_system.current_dir();
+        match dir {
+            FileSystem::ROOT => format!("/{}", name),
+            _ => format!("{}/{}", self.file_system.path(dir), name),
+        }
+    }
+
+    fn change_directory(&mut self, line: usize, cd_type: CdType) {
+        if self.lost_depth > 0 {
+            match cd_type {
+                CdType::Into { .. } => self.lost_depth += 1,
+                CdType::Up => self.lost_depth -= 1,
+                CdType::Root => {
+                    self.lost_depth = 0;
+                    self.file_system.set_current_dir(FileSystem::ROOT);
+                }
+            }
+            return;
+        }
+
+        let current_dir = self.file_system.current_dir();
+        let name = match cd_type {
+            CdType::Root => return self.file_system.set_current_dir(FileSystem::ROOT),
+            CdType::Up => {
+                return match self.file_system.parent(current_dir) {
+                    Some(parent) => self.file_system.set_current_dir(parent),
+                    None => self.report(line, DiagnosticKind::CdAboveRoot),
+                }
+            }
+            CdType::Into { name } => name,
+        };
+
+        let path = self.child_path(&name);
+        match self.file_system.child(current_dir, &name) {
+            Some(dir) if self.file_system.node(dir).is_dir() => {
+                self.file_system.set_current_dir(dir)
+            }
+            None if self.options == ImplicitDirs::Create => {
+                self.report(line, DiagnosticKind::ImplicitDir { path });
+                let dir = self.file_system.add_dir(name);
+                self.file_system.set_current_dir(dir);
+            }
+            Some(_) => {
+                self.report(line, DiagnosticKind::ConflictingType { path });
+                self.lost_depth = 1;
+            }
+            None => {
+                self.report(line, DiagnosticKind::UnknownCdTarget { path });
+                self.lost_depth = 1;
+            }
+        }
+    }
+
+    fn list(&mut self, line: usize) {
+        self.read_output = true;
+        if self.lost_depth > 0 {
+            return;
+        }
+        let dir = self.file_system.current_dir();
+        if !self.listed_dirs.insert(dir) {
+            let path = self.file_system.path(dir);
+            self.report(line, DiagnosticKind::RelistedDir { path });
+        }
+    }
+
+    fn add_entry(&mut self, line: usize, entry: Entry, name: &str) {
+        let dir = self.file_system.current_dir();
+        let Some(existing) = self.file_system.child(dir, name) else {
+            match entry {
+                Entry::Dir => self.file_system.add_dir(name.to_owned()),
+                Entry::File(size) => self.file_system.add_file(name.to_owned(), size),
+            };
+            return;
+        };
+        let path = self.child_path(name);
+        match (entry, &self.file_system.node(existing).file_type) {
+            (Entry::Dir, FileType::Dir { .. }) => {}
+            (Entry::File(seen), &FileType::File { size: kept }) => {
+                if seen != kept {
+                    self.report(line, DiagnosticKind::ConflictingSize { path, kept, seen });
+                }
+            }
+            _ => self.report(line, DiagnosticKind::ConflictingType { path }),
+        }
+    }
+
+    fn interpret(&mut self, line_number: usize, line: &str) {
+        if line.starts_with('$') {
+            self.read_output = false;
+            match Command::from_str(line) {
+                Ok(Command::CD { cd_type }) => self.change_directory(line_number, cd_type),
+                Ok(Command::LS) => self.list(line_number),
+                Err(_) => self.report(
+                    line_number,
+                    DiagnosticKind::UnknownCommand {
+                        text: line.to_owned(),
+                    },
+                ),
+            }
+        } else if !self.read_output {
+            let text = line.to_owned();
+            self.report(line_number, DiagnosticKind::OutputWithoutLs { text });
+        } else if self.lost_depth == 0 {
+            match parse_entry(line) {
+                Some((entry, name)) => self.add_entry(line_number, entry, name),
+                None => {
+                    let text = line.to_owned();
+                    self.report(line_number, DiagnosticKind::UnrecognizedOutput { text });
+                }
+            }
+        }
+    }
+}
+
+// Rebuilds the file system from the terminal output. Problems found on the way are
+// reported as diagnostics and the offending lines are skipped.
+pub fn interpret<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    implicit_dirs: ImplicitDirs,
+) -> Interpretation {
+    let mut interpreter = Interpreter {
+        options: implicit_dirs,
+        file_system: FileSystem::new(),
+        diagnostics: vec![],
+        listed_dirs: HashSet::new(),
+        read_output: false,
+        lost_depth: 0,
+    };
+    for (idx, line) in lines.into_iter().enumerate() {
+        let line = line.as_ref();
+        if !line.trim().is_empty() {
+            interpreter.interpret(idx + 1, line);
+        }
+    }
+    Interpretation {
+        file_system: interpreter.file_system,
+        diagnostics: interpreter.diagnostics,
+    }
+}
+
+// Rebuilds the file system, fails on the first error that was found.
+pub fn read_transcript<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+) -> Result<FileSystem, String> {
+    let interpretation = interpret(lines, ImplicitDirs::Create);
+    match interpretation
+        .diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.severity() == Severity::Error)
+    {
+        Some(diagnostic) => Err(diagnostic.to_string()),
+        None => Ok(interpretation.file_system),
+    }
+}
@@ -0,0 +1,678 @@
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const ORTHOGONAL: [Direction; 4] = [Self::Left, Self::Right, Self::Up, Self::Down];
+    pub const ALL: [Direction; 8] = [
+        Self::Left,
+        Self::Right,
+        Self::Up,
+        Self::Down,
+        Self::UpLeft,
+        Self::UpRight,
+        Self::DownLeft,
+        Self::DownRight,
+    ];
+
+    // Row and column step of a walk in the direction.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Self::Left => (0, -1),
+            Self::Right => (0, 1),
+            Self::Up => (-1, 0),
+            Self::Down => (1, 0),
+            Self::UpLeft => (-1, -1),
+            Self::UpRight => (-1, 1),
+            Self::DownLeft => (1, -1),
+            Self::DownRight => (1, 1),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// Which trees stop the view of a tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blocking {
+    // trees at least as tall as the viewer, the puzzle's rule
+    #[default]
+    AtLeastAsTall,
+    // only trees taller than the viewer
+    Taller,
+}
+
+impl FromStr for Blocking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ge" => Ok(Self::AtLeastAsTall),
+            "gt" => Ok(Self::Taller),
+            _ => Err(format!("Unknown blocking rule {}!", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ViewRules {
+    // look along the diagonals too
+    pub diagonals: bool,
+    pub blocking: Blocking,
+}
+
+impl ViewRules {
+    pub fn directions(&self) -> &'static [Direction] {
+        if self.diagonals {
+            &Direction::ALL
+        } else {
+            &Direction::ORTHOGONAL
+        }
+    }
+}
+
+pub const MAX_SIDE: usize = u16::MAX as usize + 1;
+
+// Tree heights stored row by row.
+pub struct Forest {
+    rows: usize,
+    cols: usize,
+    heights: Vec<u32>,
+}
+
+impl FromStr for Forest {
+    type Err = String;
+
+    // Reads a map with a single digit per tree.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut heights = vec![];
+        let mut rows = 0;
+        let mut cols = 0;
+        for (line_idx, line) in s.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+            for (col_idx, height) in line.chars().enumerate() {
+                let height = height.to_digit(10).ok_or_else(|| {
+                    format!(
+                        "Line {}, column {}: '{}' is not a tree height!",
+                        line_idx + 1,
+                        col_idx + 1,
+                        height
+                    )
+                })?;
+                heights.push(height);
+            }
+            let row_len = line.chars().count();
+            if rows == 0 {
+                cols = row_len;
+            } else if row_len != cols {
+                return Err(format!(
+                    "Line {}: expected {} trees but got {}!",
+                    line_idx + 1,
+                    cols,
+                    row_len
+                ));
+            }
+            rows += 1;
+        }
+        Forest::new(rows, cols, heights)
+    }
+}
+
+impl Forest {
+    pub fn new(rows: usize, cols: usize, heights: Vec<u32>) -> Result<Self, String> {
+        if rows == 0 || cols == 0 {
+            return Err("The forest is empty!".to_owned());
+        }
+        // viewing distances are kept as u16 to save memory on huge maps
+        if rows.max(cols) > MAX_SIDE {
+            return Err(format!(
+                "The forest can't be longer than {} trees on a side!",
+                MAX_SIDE
+            ));
+        }
+        if heights.len() != rows * cols {
+            return Err(format!(
+                "A {}x{} forest needs {} trees but got {}!",
+                rows,
+                cols,
+                rows * cols,
+                heights.len()
+            ));
+        }
+        Ok(Self {
+            rows,
+            cols,
+            heights,
+        })
+    }
+
+    // Reads a map of multi-digit heights separated by commas or whitespace.
+    pub fn parse_delimited(s: &str) -> Result<Self, String> {
+        let mut heights = vec![];
+        let mut rows = 0;
+        let mut cols = 0;
+        for (line_idx, line) in s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+        {
+            let row_len = heights.len();
+            for (col_idx, height) in line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|height| !height.is_empty())
+                .enumerate()
+            {
+                heights.push(height.parse::<u32>().map_err(|_| {
+                    format!(
+                        "Line {}, tree {}: '{}' is not a tree height!",
+                        line_idx + 1,
+                        col_idx + 1,
+                        height
+                    )
+                })?);
+            }
+            let row_len = heights.len() - row_len;
+            if rows == 0 {
+                cols = row_len;
+            } else if row_len != cols {
+                return Err(format!(
+                    "Line {}: expected {} trees but got {}!",
+                    line_idx + 1,
+                    cols,
+                    row_len
+                ));
+            }
+            rows += 1;
+        }
+        Forest::new(rows, cols, heights)
+    }
+
+    // Random forest of single digit heights, the same seed gives the same forest.
+    pub fn generate(rows: usize, cols: usize, seed: u64) -> Result<Self, String> {
+        // xorshift64*, good enough for test data
+        let mut state = seed.max(1);
+        let heights = (0..rows * cols)
+            .map(|_| {
+                state ^= state >> 12;
+                state ^= state << 25;
+                state ^= state >> 27;
+                (state.wrapping_mul(0x2545F4914F6CDD1D) >> 32) as u32 % 10
+            })
+            .collect();
+        Forest::new(rows, cols, heights)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn height(&self, row: usize, col: usize) -> u32 {
+        self.heights[row * self.cols + col]
+    }
+
+    // Computes visibility and viewing distances of every tree with the puzzle's rules.
+    pub fn analyze(&self) -> Analysis {
+        self.analyze_with(&ViewRules::default())
+    }
+
+    // Computes visibility and viewing distances of every tree, in linear
+    // time for any rules.
+    pub fn analyze_with(&self, rules: &ViewRules) -> Analysis {
+        let (rows, cols) = (self.rows, self.cols);
+        let mut visible_from = vec![0; rows * cols];
+        let mut distances = vec![vec![]; Direction::ALL.len()];
+        let tallest = *self.heights.iter().max().unwrap();
+        // bytes make the sweeps a lot quicker on huge maps
+        let bytes: Option<Vec<u8>> = (tallest <= u8::MAX as u32)
+            .then(|| self.heights.iter().map(|&height| height as u8).collect());
+        // heights and visibility of the map turned over
+        let mut turned: Option<(Vec<u8>, Vec<u8>)> = None;
+        let mut table = vec![];
+        for &direction in rules.directions() {
+            distances[direction.index()] = match bytes {
+                Some(ref heights) if tallest < ROW_SWEEP_LEVELS => {
+                    let levels = tallest as u8 + 1;
+                    match direction.offset().0 {
+                        // the trees looking left or right are swept on the
+                        // map turned over, a column at a time
+                        0 => {
+                            let (heights, visible) = turned.get_or_insert_with(|| {
+                                (transpose(heights, cols), vec![0; rows * cols])
+                            });
+                            let map = Map {
+                                heights,
+                                cols: rows,
+                                turned: true,
+                            };
+                            let distances = sweep_rows(
+                                map,
+                                levels,
+                                direction,
+                                rules.blocking,
+                                &mut table,
+                                visible,
+                            );
+                            transpose(&distances, rows)
+                        }
+                        _ => {
+                            let map = Map {
+                                heights,
+                                cols,
+                                turned: false,
+                            };
+                            let visible = &mut visible_from;
+                            sweep_rows(map, levels, direction, rules.blocking, &mut table, visible)
+                        }
+                    }
+                }
+                Some(ref heights) => {
+                    sweep_lines(heights, cols, direction, rules.blocking, &mut visible_from)
+                }
+                None => sweep_lines(
+                    &self.heights,
+                    cols,
+                    direction,
+                    rules.blocking,
+                    &mut visible_from,
+                ),
+            };
+        }
+        if let Some((_, visible)) = turned {
+            for (mask, turned_mask) in visible_from.iter_mut().zip(transpose(&visible, rows)) {
+                *mask |= turned_mask;
+            }
+        }
+        Analysis {
+            rows,
+            cols,
+            directions: rules.directions(),
+            visible_from,
+            distances,
+        }
+    }
+}
+
+// Maps with fewer heights than this are swept a row at a time, above it
+// following the blockers is quicker.
+const ROW_SWEEP_LEVELS: u32 = 16;
+
+// Heights of a map stored row by row, or column by column if it's turned, the
+// results of the sweep are then turned as well.
+struct Map<'a> {
+    heights: &'a [u8],
+    cols: usize,
+    turned: bool,
+}
+
+fn transpose<T: Copy + Default>(values: &[T], cols: usize) -> Vec<T> {
+    let rows = values.len() / cols;
+    let mut turned = vec![T::default(); values.len()];
+    // in blocks so that both sides stay in the cache
+    const BLOCK: usize = 64;
+    for first_row in (0..rows).step_by(BLOCK) {
+        let last_row = rows.min(first_row + BLOCK);
+        for first_col in (0..cols).step_by(BLOCK) {
+            for col in first_col..cols.min(first_col + BLOCK) {
+                let turned = &mut turned[col * rows + first_row..col * rows + last_row];
+                for (row, value) in (first_row..).zip(turned) {
+                    *value = values[row * cols + col];
+                }
+            }
+        }
+    }
+    turned
+}
+
+// Sweeps the trees looking up or down, straight or along a diagonal, a whole
+// map row at a time: every line that crosses the row moves on by a tree. The
+// monotonic stack of a line is kept flattened by height, `table` holds for
+// every height and line the step of the latest tree at least that tall, and
+// the level above the tallest tree keeps the step of the edge. There are no
+// branches on the heights in the inner loops, so they turn into vector code.
+fn sweep_rows(
+    map: Map,
+    levels: u8,
+    direction: Direction,
+    blocking: Blocking,
+    table: &mut Vec<u16>,
+    visible: &mut [u8],
+) -> Vec<u16> {
+    let cols = map.cols;
+    let rows = map.heights.len() / cols;
+    let (row_step, col_step) = match map.turned {
+        true => (direction.offset().1, direction.offset().0),
+        false => direction.offset(),
+    };
+    let bit = 1 << direction.index();
+    let lines = rows + cols;
+    let edge = levels as usize;
+    table.clear();
+    table.resize((edge + 1) * lines, 0);
+    // level of the table with the tree blocking the view of a tree
+    let above = (blocking == Blocking::Taller) as u8;
+    let mut distances = vec![0; rows * cols];
+    let mut blockers = vec![0; cols];
+    for step in 0..rows {
+        let row = if row_step == 1 { rows - 1 - step } else { step };
+        // line of the first column, a diagonal one is new on the side it
+        // looks at
+        let first = match col_step {
+            -1 => rows - 1 - step,
+            1 => step,
+            _ => 0,
+        };
+        if step > 0 && col_step != 0 {
+            let new = if col_step == -1 {
+                first
+            } else {
+                first + cols - 1
+            };
+            for level in 0..=edge {
+                table[level * lines + new] = step as u16;
+            }
+        }
+        // steps are counted from 1 and wrap on the longest lines, only the
+        // differences matter
+        let pos = (step as u16).wrapping_add(1);
+
+        let heights = &map.heights[row * cols..][..cols];
+        let blockers = &mut blockers[..cols];
+        for level in 0..=edge {
+            let latest = &mut table[level * lines + first..][..cols];
+            let level = level as u8;
+            for col in 0..cols {
+                let is_blocker = (heights[col] + above == level) as u16;
+                blockers[col] |= latest[col] & is_blocker.wrapping_neg();
+            }
+            for col in 0..cols {
+                let is_lower = (heights[col] < level) as u16;
+                latest[col] =
+                    (latest[col] & is_lower.wrapping_neg()) | (pos & is_lower.wrapping_sub(1));
+            }
+        }
+
+        let edges = &table[edge * lines + first..][..cols];
+        for col in 0..cols {
+            let sees_edge = blockers[col] == edges[col];
+            let idx = row * cols + col;
+            distances[idx] = pos
+                .wrapping_sub(blockers[col])
+                .wrapping_sub(sees_edge as u16);
+            visible[idx] |= bit * sees_edge as u8;
+            blockers[col] = 0;
+        }
+    }
+    distances
+}
+
+// Sweeps the trees one line after another following the blockers, see
+// `follow_blockers`.
+fn sweep_lines<H: Copy + Ord>(
+    heights: &[H],
+    cols: usize,
+    direction: Direction,
+    blocking: Blocking,
+    visible: &mut [u8],
+) -> Vec<u16> {
+    let sweep = match blocking {
+        Blocking::AtLeastAsTall => sweep_for::<H, false>(direction),
+        Blocking::Taller => sweep_for::<H, true>(direction),
+    };
+    let mut distances = vec![0; heights.len()];
+    sweep(
+        heights,
+        cols,
+        1 << direction.index(),
+        visible,
+        &mut distances,
+    );
+    distances
+}
+
+type Sweep<H> = fn(&[H], usize, u8, &mut [u8], &mut [u16]);
+
+fn sweep_for<H: Copy + Ord, const TALLER: bool>(direction: Direction) -> Sweep<H> {
+    match direction {
+        Direction::Left => follow_blockers::<H, 0, -1, TALLER>,
+        Direction::Right => follow_blockers::<H, 0, 1, TALLER>,
+        Direction::Up => follow_blockers::<H, -1, 0, TALLER>,
+        Direction::Down => follow_blockers::<H, 1, 0, TALLER>,
+        Direction::UpLeft => follow_blockers::<H, -1, -1, TALLER>,
+        Direction::UpRight => follow_blockers::<H, -1, 1, TALLER>,
+        Direction::DownLeft => follow_blockers::<H, 1, -1, TALLER>,
+        Direction::DownRight => follow_blockers::<H, 1, 1, TALLER>,
+    }
+}
+
+// Sets the viewing distances of the trees looking `(ROW_STEP, COL_STEP)` and
+// their `bit` in `visible` if they see the edge. The map is walked from that
+// edge, so the trees a tree looks at are done before it. The trees that block
+// the view along a line are a monotonic stack, which is kept in the distances:
+// from a tree that doesn't block the view the walk jumps straight to the one
+// that blocked its own view, everything in between is lower. Each tree is
+// jumped over at most once per line, so it's linear and allocates nothing.
+fn follow_blockers<
+    H: Copy + Ord,
+    const ROW_STEP: isize,
+    const COL_STEP: isize,
+    const TALLER: bool,
+>(
+    heights: &[H],
+    cols: usize,
+    bit: u8,
+    visible: &mut [u8],
+    distances: &mut [u16],
+) {
+    let rows = heights.len() / cols;
+    // from a tree to the next one it looks at
+    let step = ROW_STEP * cols as isize + COL_STEP;
+    for row_idx in 0..rows {
+        let row = if ROW_STEP == 1 {
+            rows - 1 - row_idx
+        } else {
+            row_idx
+        };
+        for col_idx in 0..cols {
+            let col = if COL_STEP == 1 {
+                cols - 1 - col_idx
+            } else {
+                col_idx
+            };
+            let idx = row * cols + col;
+            if (ROW_STEP != 0 && row_idx == 0) || (COL_STEP != 0 && col_idx == 0) {
+                visible[idx] |= bit;
+                continue;
+            }
+            let height = heights[idx];
+            let mut next = idx.wrapping_add_signed(step);
+            let mut distance = 1;
+            loop {
+                let blocks = match TALLER {
+                    true => heights[next] > height,
+                    false => heights[next] >= height,
+                };
+                if blocks {
+                    break;
+                }
+                distance += distances[next];
+                if visible[next] & bit != 0 {
+                    visible[idx] |= bit;
+                    break;
+                }
+                next = next.wrapping_add_signed(step * distances[next] as isize);
+            }
+            distances[idx] = distance;
+        }
+    }
+}
+
+// Per tree results of `Forest::analyze`.
+pub struct Analysis {
+    rows: usize,
+    cols: usize,
+    directions: &'static [Direction],
+    // bit mask of the directions from which the tree is visible
+    visible_from: Vec<u8>,
+    // indexed by the direction, empty for the directions that weren't analyzed
+    distances: Vec<Vec<u16>>,
+}
+
+impl Analysis {
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn directions(&self) -> &'static [Direction] {
+        self.directions
+    }
+
+    pub fn is_visible_from(&self, row: usize, col: usize, direction: Direction) -> bool {
+        self.visible_from[row * self.cols + col] & (1 << direction.index()) != 0
+    }
+
+    pub fn is_visible(&self, row: usize, col: usize) -> bool {
+        self.visible_from[row * self.cols + col] != 0
+    }
+
+    // Number of trees that can be seen from the tree in the given direction,
+    // zero for a direction that wasn't analyzed.
+    pub fn viewing_distance(&self, row: usize, col: usize, direction: Direction) -> u16 {
+        self.distances[direction.index()]
+            .get(row * self.cols + col)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Product of the viewing distances in all the analyzed directions, eight
+    // u16 distances always fit in a u128.
+    pub fn scenic_score(&self, row: usize, col: usize) -> u128 {
+        self.directions
+            .iter()
+            .map(|&direction| self.viewing_distance(row, col, direction) as u128)
+            .product()
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.visible_from.iter().filter(|&&mask| mask != 0).count()
+    }
+
+    // Position and score of the tree with the best scenic score, the first one on ties.
+    pub fn best_scenic_spot(&self) -> (usize, usize, u128) {
+        let mut best = (0, 0, 0);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let score = self.scenic_score(row, col);
+                if score > best.2 {
+                    best = (row, col, score);
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    // Walks out from every tree, the way the puzzle explains it.
+    fn check_against_walking(forest: &Forest) {
+        for diagonals in [false, true] {
+            for blocking in [Blocking::AtLeastAsTall, Blocking::Taller] {
+                let rules = ViewRules {
+                    diagonals,
+                    blocking,
+                };
+                let analysis = forest.analyze_with(&rules);
+                for row in 0..forest.rows() {
+                    for col in 0..forest.cols() {
+                        let height = forest.height(row, col);
+                        for &direction in rules.directions() {
+                            let (row_step, col_step) = direction.offset();
+                            let (mut r, mut c, mut seen) = (row, col, 0);
+                            let sees_edge = loop {
+                                let (Some(next_r), Some(next_c)) = (
+                                    r.checked_add_signed(row_step)
+                                        .filter(|&r| r < forest.rows()),
+                                    c.checked_add_signed(col_step)
+                                        .filter(|&c| c < forest.cols()),
+                                ) else {
+                                    break true;
+                                };
+                                (r, c, seen) = (next_r, next_c, seen + 1);
+                                let other = forest.height(r, c);
+                                if other > height
+                                    || (other == height && blocking == Blocking::AtLeastAsTall)
+                                {
+                                    break false;
+                                }
+                            };
+                            let at = format!("{:?} at {},{} with {:?}", direction, row, col, rules);
+                            assert_eq!(
+                                analysis.viewing_distance(row, col, direction),
+                                seen,
+                                "{}",
+                                at
+                            );
+                            assert_eq!(
+                                analysis.is_visible_from(row, col, direction),
+                                sees_edge,
+                                "{}",
+                                at
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn digit_maps_match_walking_out() {
+        check_against_walking(&Forest::generate(37, 23, 5).unwrap());
+        check_against_walking(&Forest::generate(1, 17, 6).unwrap());
+        check_against_walking(&Forest::generate(17, 1, 7).unwrap());
+        check_against_walking(&"30373\n25512\n65332\n33549\n35390".parse().unwrap());
+    }
+
+    #[test]
+    fn tall_maps_match_walking_out() {
+        let forest = Forest::generate(29, 31, 8).unwrap();
+        for scale in [7, 1000] {
+            let heights = forest.heights.iter().map(|height| height * scale).collect();
+            check_against_walking(&Forest::new(29, 31, heights).unwrap());
+        }
+    }
+
+    // Run with `cargo test --release`, it takes ages without optimizations.
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn analyzes_5000x5000_quickly() {
+        let forest = Forest::generate(5000, 5000, 1).unwrap();
+        let start = Instant::now();
+        let analysis = forest.analyze();
+        let elapsed = start.elapsed();
+        assert_eq!(analysis.visible_count(), 58642);
+        assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+    }
+}
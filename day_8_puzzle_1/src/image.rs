@@ -0,0 +1,186 @@
+use crate::forest::{Analysis, Forest};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const SIGHT_LINE: [u8; 3] = [0, 255, 255];
+const BEST_TREE: [u8; 3] = [0, 255, 0];
+
+// 8-bit raster, either grayscale or RGB.
+pub struct Image {
+    width: usize,
+    height: usize,
+    channels: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn gray(width: usize, height: usize) -> Self {
+        Self::new(width, height, 1)
+    }
+
+    pub fn rgb(width: usize, height: usize) -> Self {
+        Self::new(width, height, 3)
+    }
+
+    fn new(width: usize, height: usize, channels: usize) -> Self {
+        Self {
+            width,
+            height,
+            channels,
+            pixels: vec![0; width * height * channels],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_rgb(&self) -> bool {
+        self.channels == 3
+    }
+
+    // Sets a pixel, a grayscale image takes the first component of the colour only.
+    pub fn set(&mut self, x: usize, y: usize, colour: &[u8]) {
+        let start = (y * self.width + x) * self.channels;
+        self.pixels[start..start + self.channels].copy_from_slice(&colour[..self.channels]);
+    }
+
+    // Paints a block of `scale`x`scale` pixels that represents a single tree.
+    fn set_cell(&mut self, row: usize, col: usize, scale: usize, colour: &[u8]) {
+        for y in row * scale..(row + 1) * scale {
+            for x in col * scale..(col + 1) * scale {
+                self.set(x, y, colour);
+            }
+        }
+    }
+
+    // Binary PGM for grayscale or PPM for RGB images.
+    pub fn write_pnm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let magic = if self.is_rgb() { "P6" } else { "P5" };
+        write!(writer, "{}\n{} {}\n255\n", magic, self.width, self.height)?;
+        writer.write_all(&self.pixels)?;
+        writer.flush()
+    }
+
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(if self.is_rgb() {
+            png::ColorType::Rgb
+        } else {
+            png::ColorType::Grayscale
+        });
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    // Writes a PNG or a netpbm file depending on the extension of the path.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => self.write_png(writer),
+            Some("pgm") | Some("ppm") | Some("pnm") => self.write_pnm(writer),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown image format of {}!", path.display()),
+            )),
+        }
+    }
+}
+
+// Tree heights as shades of gray, the tallest tree is white.
+pub fn heights_image(forest: &Forest, scale: usize) -> Image {
+    let mut image = Image::gray(forest.cols() * scale, forest.rows() * scale);
+    let max_height = (0..forest.rows())
+        .flat_map(|row| (0..forest.cols()).map(move |col| forest.height(row, col)))
+        .max()
+        .unwrap_or(0)
+        .max(1) as u64;
+    for row in 0..forest.rows() {
+        for col in 0..forest.cols() {
+            let shade = (forest.height(row, col) as u64 * 255 / max_height) as u8;
+            image.set_cell(row, col, scale, &[shade]);
+        }
+    }
+    image
+}
+
+// Visible trees are white, hidden ones black.
+pub fn visibility_image(analysis: &Analysis, scale: usize) -> Image {
+    let mut image = Image::gray(analysis.cols() * scale, analysis.rows() * scale);
+    for row in 0..analysis.rows() {
+        for col in 0..analysis.cols() {
+            let shade = if analysis.is_visible(row, col) {
+                255
+            } else {
+                0
+            };
+            image.set_cell(row, col, scale, &[shade]);
+        }
+    }
+    image
+}
+
+// Maps 0.0..=1.0 onto a black, purple, red, yellow, white colour scale.
+pub fn colour_scale(t: f64) -> [u8; 3] {
+    const STOPS: [[f64; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [90.0, 20.0, 130.0],
+        [220.0, 50.0, 40.0],
+        [250.0, 200.0, 30.0],
+        [255.0, 255.0, 255.0],
+    ];
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let idx = (t.floor() as usize).min(STOPS.len() - 2);
+    let fraction = t - idx as f64;
+    let mut colour = [0; 3];
+    for (channel, value) in colour.iter_mut().enumerate() {
+        let (from, to) = (STOPS[idx][channel], STOPS[idx + 1][channel]);
+        *value = (from + (to - from) * fraction).round() as u8;
+    }
+    colour
+}
+
+// Scenic scores on a logarithmic colour scale with a legend strip below the map.
+// The best tree is green and the trees it can see are cyan.
+pub fn scenic_heatmap(analysis: &Analysis, scale: usize) -> Image {
+    let (rows, cols) = (analysis.rows(), analysis.cols());
+    let legend = (2 * scale).max(4);
+    let mut image = Image::rgb(cols * scale, rows * scale + legend);
+    let (best_row, best_col, best_score) = analysis.best_scenic_spot();
+    let max_log = ((best_score + 1) as f64).ln().max(f64::MIN_POSITIVE);
+    for row in 0..rows {
+        for col in 0..cols {
+            let score = analysis.scenic_score(row, col);
+            let colour = colour_scale(((score + 1) as f64).ln() / max_log);
+            image.set_cell(row, col, scale, &colour);
+        }
+    }
+
+    for &direction in analysis.directions() {
+        let (row_step, col_step) = direction.offset();
+        for step in 1..=analysis.viewing_distance(best_row, best_col, direction) as isize {
+            let row = (best_row as isize + row_step * step) as usize;
+            let col = (best_col as isize + col_step * step) as usize;
+            image.set_cell(row, col, scale, &SIGHT_LINE);
+        }
+    }
+    image.set_cell(best_row, best_col, scale, &BEST_TREE);
+
+    let width = image.width();
+    for x in 0..width {
+        let colour = colour_scale(x as f64 / (width.max(2) - 1) as f64);
+        for y in rows * scale + legend / 2..image.height() {
+            image.set(x, y, &colour);
+        }
+    }
+    image
+}
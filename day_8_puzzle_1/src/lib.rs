@@ -0,0 +1,2 @@
+pub mod forest;
+pub mod image;
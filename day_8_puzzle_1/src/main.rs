@@ -1,52 +1,160 @@
-use std::io::{BufRead, BufReader};
-use std::{fs::File, io};
+use day_8_puzzle_1::forest::{Blocking, Forest, ViewRules};
+use day_8_puzzle_1::image::{heights_image, scenic_heatmap, visibility_image};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Instant;
+use std::{fs, io};
 
+// Usage: day_8_puzzle_1 <input> [options]
+//        day_8_puzzle_1 --generate <rows> <cols> [<seed>] [options]
+// Options:
+//   --delimited           the map holds multi-digit heights separated by commas or spaces
+//   --diagonals           trees look along the diagonals too
+//   --blocking ge|gt      trees at least as tall (default) or only taller ones block the view
+//   --compare-rules       reports the results of all the viewing rules
+//   --export <prefix>     writes <prefix>_heights, <prefix>_visibility and <prefix>_scenic images
+//   --format png|pnm      format of the exported images, png by default
+//   --scale <n>           size of a tree in pixels, 1 by default
+//   --time                reports how long the analysis took
 fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
+    let mut args = std::env::args().skip(1).peekable();
+    let source = args.next().expect("No input file given!");
+    let mut generate = None;
+    if source == "--generate" {
+        let mut number = || -> usize {
+            args.next()
+                .and_then(|arg| arg.parse().ok())
+                .expect("--generate expects the number of rows and columns!")
+        };
+        let (rows, cols) = (number(), number());
+        let seed = match args.peek() {
+            Some(arg) if !arg.starts_with("--") => args
+                .next()
+                .unwrap()
+                .parse()
+                .expect("The seed has to be a number!"),
+            _ => 1,
+        };
+        generate = Some((rows, cols, seed));
+    }
+    let mut delimited = false;
+    let mut rules = ViewRules::default();
+    let mut compare_rules = false;
+    let mut export = None;
+    let mut format = "png".to_owned();
+    let mut scale = 1;
+    let mut time = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--delimited" => delimited = true,
+            "--diagonals" => rules.diagonals = true,
+            "--blocking" => {
+                rules.blocking =
+                    Blocking::from_str(&args.next().expect("No blocking rule given!")).unwrap()
+            }
+            "--compare-rules" => compare_rules = true,
+            "--export" => export = Some(args.next().expect("No export prefix given!")),
+            "--format" => format = args.next().expect("No image format given!"),
+            "--scale" => {
+                scale = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n| n > 0)
+                    .expect("--scale expects a positive number!")
+            }
+            "--time" => time = true,
+            _ => panic!("Unknown argument {}!", arg),
+        }
+    }
+
+    let forest = match generate {
+        Some((rows, cols, seed)) => {
+            Forest::generate(rows, cols, seed).expect("Couldn't generate the forest!")
+        }
+        None => {
+            let map = fs::read_to_string(source)?;
+            match delimited {
+                true => Forest::parse_delimited(&map),
+                false => Forest::from_str(&map),
+            }
+            .expect("Couldn't read the map!")
+        }
+    };
 
-    let mut forest_map = Vec::<Vec<u8>>::new();
-    for result_line in reader.lines() {
-        let line = result_line.expect("Couldn't read a row!");
-        forest_map.push(
-            line.chars()
-                .map(|height| height as u8 - '0' as u8)
-                .collect(),
+    let start = Instant::now();
+    let analysis = forest.analyze_with(&rules);
+    let num_of_visible = analysis.visible_count();
+    if time {
+        eprintln!(
+            "Analyzed {}x{} trees in {:?}",
+            forest.rows(),
+            forest.cols(),
+            start.elapsed()
         );
     }
 
-    let mut num_of_visible = 0;
-    let n_rows = forest_map.len();
-    let n_cols = forest_map[0].len();
-    for row_idx in 0..n_rows {
-        for col_idx in 0..n_cols {
-            if row_idx == 0 || row_idx == n_rows - 1 || col_idx == 0 || col_idx == n_cols - 1 {
-                num_of_visible += 1;
-            } else {
-                let tree_size = forest_map[row_idx][col_idx];
-                if forest_map[row_idx][0..col_idx]
-                    .iter()
-                    .all(|tree| *tree < tree_size) // visible from left?
-                    || forest_map[row_idx][col_idx + 1..n_cols]
-                        .iter()
-                        .all(|tree| *tree < tree_size) // visible from right
-                    || forest_map[0..row_idx]
-                        .iter()
-                        .map(|rows| rows[col_idx])
-                        .all(|tree| tree < tree_size) // visible from up
-                    || forest_map[row_idx + 1..n_rows]
-                        .iter()
-                        .map(|rows| rows[col_idx])
-                        .all(|tree| tree < tree_size) // visible from down
-                {
-                    num_of_visible += 1;
-                }
+    println!("N visible trees: {}", num_of_visible);
+
+    if compare_rules {
+        let mut results = vec![];
+        for diagonals in [false, true] {
+            for blocking in [Blocking::AtLeastAsTall, Blocking::Taller] {
+                let analysis = forest.analyze_with(&ViewRules {
+                    diagonals,
+                    blocking,
+                });
+                results.push((
+                    diagonals,
+                    blocking,
+                    analysis.visible_count(),
+                    analysis.best_scenic_spot(),
+                ));
             }
         }
+        // scores with diagonals can get long
+        let score_width = results
+            .iter()
+            .map(|(_, _, _, (_, _, score))| score.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max(14);
+        println!(
+            "{:<11} {:<9} {:>10} {:>score_width$}  best tree",
+            "directions", "blocking", "visible", "top score"
+        );
+        for (diagonals, blocking, visible, (row, col, score)) in results {
+            println!(
+                "{:<11} {:<9} {:>10} {:>score_width$}  row {}, col {}",
+                if diagonals { "8" } else { "4" },
+                match blocking {
+                    Blocking::AtLeastAsTall => ">=",
+                    Blocking::Taller => ">",
+                },
+                visible,
+                score,
+                row + 1,
+                col + 1
+            );
+        }
     }
 
-    println!("N visible trees: {}", num_of_visible);
+    if let Some(prefix) = export {
+        let (gray, colour) = match format.as_str() {
+            "png" => ("png", "png"),
+            "pnm" => ("pgm", "ppm"),
+            _ => panic!("Unknown image format {}!", format),
+        };
+        let images = [
+            ("heights", gray, heights_image(&forest, scale)),
+            ("visibility", gray, visibility_image(&analysis, scale)),
+            ("scenic", colour, scenic_heatmap(&analysis, scale)),
+        ];
+        for (name, extension, image) in images {
+            let path = PathBuf::from(format!("{}_{}.{}", prefix, name, extension));
+            image.save(&path)?;
+            println!("Written {}", path.display());
+        }
+    }
 
     Ok(())
 }
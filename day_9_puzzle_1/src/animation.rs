@@ -0,0 +1,126 @@
+use crate::render::{all_trails, Bounds};
+use crate::rope::Rope;
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+const BACKGROUND: u8 = 0;
+const TRAIL: u8 = 1;
+const START: u8 = 2;
+const HEAD: u8 = 3;
+// the rest of the knots fade from the first to the last of these
+const FIRST_KNOT: u8 = 4;
+const KNOT_SHADES: u8 = 16;
+
+fn palette() -> Vec<u8> {
+    let mut palette = vec![
+        20, 20, 30, // background
+        90, 90, 140, // tail trail
+        255, 255, 255, // start
+        255, 60, 40, // head
+    ];
+    for shade in 0..KNOT_SHADES as u32 {
+        // orange towards yellow
+        palette.extend([255, (140 + shade * 115 / (KNOT_SHADES as u32 - 1)) as u8, 0]);
+    }
+    palette
+}
+
+fn knot_colour(knot: usize, num_of_knots: usize) -> u8 {
+    if knot == 0 {
+        return HEAD;
+    }
+    let shade = (knot - 1) * (KNOT_SHADES as usize - 1) / (num_of_knots - 2).max(1);
+    FIRST_KNOT + shade as u8
+}
+
+// Options of the animation, every step is a frame by default.
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationOptions {
+    // pixels per grid cell
+    pub scale: usize,
+    // only every n-th step becomes a frame, the last step always does
+    pub every: usize,
+    // delay between frames in hundredths of a second
+    pub delay: u16,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            scale: 4,
+            every: 1,
+            delay: 5,
+        }
+    }
+}
+
+// Writes the whole run as a looping GIF, drawn like the ASCII frames: the
+// tail trail, the start and the knots with the head on top.
+pub fn write_gif<W: Write>(rope: &Rope, writer: W, options: &AnimationOptions) -> io::Result<()> {
+    let trails = all_trails(rope).map_err(io::Error::other)?;
+    let num_of_knots = trails.len();
+    let last_step = trails[0].len() - 1;
+    let bounds = Bounds::of_steps(&trails, 0..=last_step);
+    let scale = options.scale.max(1);
+    let (width, height) = (bounds.width() * scale, bounds.height() * scale);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(io::Error::other(format!(
+            "The animation would be {}x{} pixels, that's too large for a GIF!",
+            width, height
+        )));
+    }
+
+    let mut encoder = gif::Encoder::new(writer, width as u16, height as u16, &palette())
+        .map_err(io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(io::Error::other)?;
+
+    // the trail stays drawn, only the knots get erased between frames
+    let mut cells = vec![BACKGROUND; bounds.width() * bounds.height()];
+    let index = |row: usize, col: usize| row * bounds.width() + col;
+    let tail = &trails[num_of_knots - 1];
+    let steps = (0..=last_step)
+        .step_by(options.every.max(1))
+        .chain((last_step % options.every.max(1) != 0).then_some(last_step));
+    let mut trail_drawn = 0;
+    for step in steps {
+        for point in &tail[trail_drawn..=step] {
+            let (row, col) = bounds.cell(point).unwrap();
+            cells[index(row, col)] = TRAIL;
+        }
+        trail_drawn = step;
+        let mut frame_cells = cells.clone();
+        let (row, col) = bounds.cell(&Default::default()).unwrap();
+        frame_cells[index(row, col)] = START;
+        for knot in (0..num_of_knots).rev() {
+            let (row, col) = bounds.cell(&trails[knot][step]).unwrap();
+            frame_cells[index(row, col)] = knot_colour(knot, num_of_knots);
+        }
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in frame_cells.chunks(bounds.width()) {
+            let line = row
+                .iter()
+                .flat_map(|&cell| std::iter::repeat_n(cell, scale))
+                .collect::<Vec<_>>();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        let frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            buffer: Cow::Owned(pixels),
+            // hold the final state a little longer
+            delay: if step == last_step {
+                options.delay.saturating_mul(20).max(100)
+            } else {
+                options.delay
+            },
+            ..Default::default()
+        };
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
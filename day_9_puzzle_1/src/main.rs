@@ -1,124 +1,217 @@
-use std::collections::HashSet;
-use std::default::Default;
-use std::io::{BufRead, BufReader};
-use std::{fs::File, io};
+use day_9_puzzle_1::animation::{write_gif, AnimationOptions};
+use day_9_puzzle_1::render::{render_motions, render_steps, render_visited};
+use day_9_puzzle_1::rope::{
+    read_motions, Direction, Direction3, Motion, Point, Point3, Rope, Step,
+};
+use std::fs::File;
+use std::io::BufWriter;
+use std::ops::RangeInclusive;
+use std::{fs, io, process};
 
-#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
+fn exit_with(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(1);
 }
 
-impl std::ops::Sub for Point {
-    type Output = Self;
+// Usage: day_9_puzzle_1 <input> [--knots <n>] [--track <k>,<k>,...|all] [--trail <k>] [--3d]
+//        day_9_puzzle_1 <input> [--knots <n>] --check-3d
+// Knot 0 is the head, the tail is always tracked. With --3d the motions
+// move along x (R/L), y (F/B) and z (U/D).
+// Drawing the 2D rope:
+//   --frames all|<step>|<from>..<to>   draws the steps like the puzzle, step 0 is the initial state
+//   --frames motions                  draws the state after every motion
+//   --show-trail                      marks the cells the tail visited so far with #
+//   --visited                         draws all the cells the tail visited
+//   --animate <file.gif>              writes the whole run as an animation
+//   --every <n> --scale <n> --delay <n>  frame step, pixels per cell and delay (1/100 s)
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input_path = args.next().expect("No input file given!");
+    let mut num_of_knots: usize = 2;
+    let mut track = None;
+    let mut print_trail = None;
+    let mut three_d = false;
+    let mut check = false;
+    let mut frames = None;
+    let mut show_trail = false;
+    let mut visited = false;
+    let mut animate = None;
+    let mut animation = AnimationOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--knots" => {
+                num_of_knots = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--knots expects a number!")
+            }
+            "--track" => track = Some(args.next().expect("No knots to track given!")),
+            "--trail" => {
+                print_trail = Some(
+                    args.next()
+                        .and_then(|k| k.parse::<usize>().ok())
+                        .expect("--trail expects a knot number!"),
+                )
+            }
+            "--3d" => three_d = true,
+            "--check-3d" => check = true,
+            "--frames" => frames = Some(args.next().expect("No steps to draw given!")),
+            "--show-trail" => show_trail = true,
+            "--visited" => visited = true,
+            "--animate" => animate = Some(args.next().expect("No animation file given!")),
+            "--every" => animation.every = positive(args.next(), "--every"),
+            "--scale" => animation.scale = positive(args.next(), "--scale"),
+            "--delay" => {
+                animation.delay = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--delay expects a number!")
+            }
+            _ => panic!("Unknown argument {}!", arg),
+        }
+    }
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        return Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        };
+    let mut tracked = match track.as_deref() {
+        None => vec![],
+        Some("all") => (0..num_of_knots).collect(),
+        Some(list) => list
+            .split(',')
+            .map(|k| {
+                k.trim()
+                    .parse()
+                    .unwrap_or_else(|_| exit_with(&format!("Not a knot number: {}", k)))
+            })
+            .collect(),
+    };
+    tracked.push(num_of_knots.saturating_sub(1));
+    tracked.extend(print_trail);
+    let report = tracked.clone();
+    let drawing = frames.is_some() || animate.is_some();
+    // the whole rope is drawn and the visited cells are framed by it
+    if drawing || visited {
+        tracked.extend(0..num_of_knots);
     }
-}
 
-impl Point {
-    // Moves to new position are stays at the same one to
-    // to maintain the point hovered or next to the other Point
-    fn move_next_to(self, other: &Self) -> Self {
-        let diff = other.clone() - self.clone();
-        if diff.x.abs() > 1 || diff.y.abs() > 1 {
-            if diff.x == 2 && diff.y == 0 {
-                // right
-                return Self {
-                    x: self.x + 1,
-                    y: self.y,
-                };
-            } else if diff.x == -2 && diff.y == 0 {
-                // left
-                return Self {
-                    x: self.x - 1,
-                    y: self.y,
-                };
-            } else if diff.x == 0 && diff.y == 2 {
-                // up
-                return Self {
-                    x: self.x,
-                    y: self.y + 1,
-                };
-            } else if diff.x == 0 && diff.y == -2 {
-                // down
-                return Self {
-                    x: self.x,
-                    y: self.y - 1,
-                };
-            } else if (diff.x > 1 && diff.y == 1) || (diff.x == 1 && diff.y > 1) {
-                // diag up-right
-                return Self {
-                    x: self.x + 1,
-                    y: self.y + 1,
-                };
-            } else if (diff.x < -1 && diff.y == 1) || (diff.x == -1 && diff.y > 1) {
-                // diag up-left
-                return Self {
-                    x: self.x - 1,
-                    y: self.y + 1,
-                };
-            } else if (diff.x > 1 && diff.y == -1) || (diff.x == 1 && diff.y < -1) {
-                // diag down-right
-                return Self {
-                    x: self.x + 1,
-                    y: self.y - 1,
-                };
-            } else if (diff.x < -1 && diff.y == -1) || (diff.x == -1 && diff.y < -1) {
-                // diag down-left
-                return Self {
-                    x: self.x - 1,
-                    y: self.y - 1,
-                };
-            }
+    let input = fs::read_to_string(input_path)?;
+    if check {
+        check_3d(&input, num_of_knots);
+    } else if three_d {
+        if drawing || visited {
+            exit_with("Only 2D ropes can be drawn!");
+        }
+        simulate::<Direction3>(&input, num_of_knots, &tracked, &report, print_trail);
+    } else {
+        let (rope, motions) =
+            simulate::<Direction>(&input, num_of_knots, &tracked, &report, print_trail);
+        if frames.as_deref() == Some("motions") {
+            let drawing =
+                render_motions(&rope, &motions, show_trail).unwrap_or_else(|err| exit_with(&err));
+            print!("{}", drawing);
+        } else if let Some(frames) = frames {
+            let last_step = rope.trail(0).unwrap().len() - 1;
+            let steps = parse_steps(&frames, last_step).unwrap_or_else(|err| exit_with(&err));
+            let drawing = render_steps(&rope, &motions, steps, show_trail)
+                .unwrap_or_else(|err| exit_with(&err));
+            print!("{}", drawing);
+        }
+        if visited {
+            print!("{}", render_visited(&rope, rope.tail()).unwrap());
+        }
+        if let Some(path) = animate {
+            write_gif(&rope, BufWriter::new(File::create(&path)?), &animation)?;
+            println!("Written {}", path);
         }
-        self
     }
+
+    Ok(())
 }
 
-fn main() -> io::Result<()> {
-    let input_path = std::env::args().nth(1).expect("No input file given!");
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
+fn positive(arg: Option<String>, flag: &str) -> usize {
+    arg.and_then(|n| n.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| exit_with(&format!("{} expects a positive number!", flag)))
+}
 
-    let mut visited_points = HashSet::<Point>::new();
-    let mut tail = Point::default();
-    let mut head = Point::default();
-    for result_line in reader.lines() {
-        let line = result_line.expect("Couldn't read a line!");
-        let mut split = line.split(" ");
-        let direction = split.next().expect("Didn't get a direction!");
-        let n_steps = split
-            .next()
-            .expect("Didn't get number of steps!")
-            .parse::<i32>()
-            .expect("Couldn't parse number of steps!");
-        for _ in 0..n_steps {
-            match direction {
-                "R" => {
-                    head.x += 1;
-                }
-                "L" => {
-                    head.x -= 1;
-                }
-                "U" => {
-                    head.y += 1;
-                }
-                "D" => {
-                    head.y -= 1;
-                }
-                _ => panic!("Unknown direction: {}!", direction),
-            }
-            tail = tail.move_next_to(&head);
-            visited_points.insert(tail.clone());
-        }
+// "all", a single step or an inclusive range of steps like "3..7".
+fn parse_steps(s: &str, last_step: usize) -> Result<RangeInclusive<usize>, String> {
+    let step = |n: &str| {
+        n.parse::<usize>()
+            .map_err(|_| format!("Not a step number: {}", n))
+    };
+    match s.split_once("..") {
+        _ if s == "all" => Ok(0..=last_step),
+        Some((from, to)) => Ok(step(from)?..=step(to)?),
+        None => step(s).map(|n| n..=n),
     }
+}
 
-    println!("N visited points: {}", visited_points.len());
+fn simulate<D: Step>(
+    input: &str,
+    num_of_knots: usize,
+    tracked: &[usize],
+    report: &[usize],
+    print_trail: Option<usize>,
+) -> (Rope<D::Knot>, Vec<Motion<D>>) {
+    let motions = read_motions::<D>(input.lines()).unwrap_or_else(|err| exit_with(&err));
+    let mut rope = Rope::with_tracked(num_of_knots, tracked).unwrap_or_else(|err| exit_with(&err));
+    for motion in &motions {
+        rope.apply(motion);
+    }
 
-    Ok(())
+    println!(
+        "N visited points: {}",
+        rope.visited(rope.tail()).unwrap().len()
+    );
+    let mut report = report.to_vec();
+    report.sort_unstable();
+    report.dedup();
+    for &knot in report.iter().filter(|&&knot| knot != rope.tail()) {
+        println!(
+            "Knot {} visited {} points",
+            knot,
+            rope.visited(knot).unwrap().len()
+        );
+    }
+    if let Some(knot) = print_trail {
+        for point in rope.trail(knot).unwrap() {
+            println!("{}", point);
+        }
+    }
+    (rope, motions)
+}
+
+// Replays the 2D motions with a 3D rope lying in the z = 0 plane (U and D
+// become F and B) and checks that every knot walks the same trail.
+fn check_3d(input: &str, num_of_knots: usize) {
+    let motions = read_motions::<Direction>(input.lines()).unwrap_or_else(|err| exit_with(&err));
+    let all_knots = (0..num_of_knots).collect::<Vec<_>>();
+    let mut flat =
+        Rope::<Point>::with_tracked(num_of_knots, &all_knots).unwrap_or_else(|err| exit_with(&err));
+    let mut spatial = Rope::<Point3>::with_tracked(num_of_knots, &all_knots).unwrap();
+    for motion in &motions {
+        flat.apply(motion);
+        spatial.apply(&Motion {
+            direction: Direction3 {
+                dx: motion.direction.dx,
+                dy: motion.direction.dy,
+                dz: 0,
+            },
+            steps: motion.steps,
+        });
+    }
+    for knot in all_knots {
+        let projected = spatial
+            .trail(knot)
+            .unwrap()
+            .iter()
+            .map(|p| (p.z == 0).then_some(Point { x: p.x, y: p.y }))
+            .collect::<Option<Vec<_>>>();
+        if projected.as_deref() != flat.trail(knot) {
+            exit_with(&format!("Knot {} moves differently in 3D!", knot));
+        }
+    }
+    println!(
+        "2D and 3D ropes agree, the tail visited {} points",
+        flat.visited(flat.tail()).unwrap().len()
+    );
 }
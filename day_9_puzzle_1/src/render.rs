@@ -0,0 +1,213 @@
+use crate::rope::{Motion, Point, Rope};
+use std::ops::RangeInclusive;
+
+// Part of the grid that is drawn, always containing the starting position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i32,
+    pub max_x: i32,
+    pub min_y: i32,
+    pub max_y: i32,
+}
+
+impl Bounds {
+    pub fn of<'a>(points: impl IntoIterator<Item = &'a Point>) -> Self {
+        let mut bounds = Self {
+            min_x: 0,
+            max_x: 0,
+            min_y: 0,
+            max_y: 0,
+        };
+        for point in points {
+            bounds.min_x = bounds.min_x.min(point.x);
+            bounds.max_x = bounds.max_x.max(point.x);
+            bounds.min_y = bounds.min_y.min(point.y);
+            bounds.max_y = bounds.max_y.max(point.y);
+        }
+        bounds
+    }
+
+    // Bounds of every knot over the given steps of the run.
+    pub fn of_steps(trails: &[&[Point]], steps: RangeInclusive<usize>) -> Self {
+        Self::of(trails.iter().flat_map(|trail| &trail[steps.clone()]))
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1) as usize
+    }
+
+    // Row and column of a point, the rows go from the top.
+    pub fn cell(&self, point: &Point) -> Option<(usize, usize)> {
+        if point.x < self.min_x
+            || point.x > self.max_x
+            || point.y < self.min_y
+            || point.y > self.max_y
+        {
+            return None;
+        }
+        Some((
+            (self.max_y - point.y) as usize,
+            (point.x - self.min_x) as usize,
+        ))
+    }
+}
+
+// H for the head and T for the tail of a two knot rope, otherwise the
+// knots are numbered like in the puzzle (a, b, ... past 9).
+pub fn knot_label(knot: usize, num_of_knots: usize) -> char {
+    match knot {
+        0 => 'H',
+        _ if num_of_knots == 2 => 'T',
+        _ => std::char::from_digit(knot as u32 % 36, 36).unwrap(),
+    }
+}
+
+// The trails of all the knots, rendering needs every one of them.
+pub fn all_trails(rope: &Rope) -> Result<Vec<&[Point]>, String> {
+    (0..rope.knots().len())
+        .map(|knot| {
+            rope.trail(knot)
+                .ok_or_else(|| format!("Knot {} is not tracked, can't draw the rope!", knot))
+        })
+        .collect()
+}
+
+// Draws the rope after the given step. Knots in front cover the ones behind
+// them and the starting position, which is noted next to the row the same
+// way the puzzle does. With `show_trail` the cells the tail visited so far
+// are marked with #.
+pub fn render_frame(trails: &[&[Point]], step: usize, bounds: &Bounds, show_trail: bool) -> String {
+    let num_of_knots = trails.len();
+    let mut grid = vec![vec!['.'; bounds.width()]; bounds.height()];
+    // what each drawn cell covers
+    let mut covers = vec![vec![Vec::<char>::new(); bounds.width()]; bounds.height()];
+    if show_trail {
+        for point in &trails[num_of_knots - 1][..=step] {
+            if let Some((row, col)) = bounds.cell(point) {
+                grid[row][col] = '#';
+            }
+        }
+    }
+    let start = Point::default();
+    if let Some((row, col)) = bounds.cell(&start) {
+        grid[row][col] = 's';
+    }
+    for knot in (0..num_of_knots).rev() {
+        let Some((row, col)) = bounds.cell(&trails[knot][step]) else {
+            continue;
+        };
+        let covered = grid[row][col];
+        if covered != '.' && covered != '#' {
+            covers[row][col].insert(0, covered);
+        }
+        grid[row][col] = knot_label(knot, num_of_knots);
+    }
+
+    let mut frame = String::new();
+    for (cells, covers) in grid.iter().zip(&covers) {
+        frame.extend(cells);
+        let notes = cells
+            .iter()
+            .zip(covers)
+            .filter(|(_, covered)| !covered.is_empty())
+            .map(|(cell, covered)| {
+                let covered = covered
+                    .iter()
+                    .map(char::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} covers {}", cell, covered)
+            })
+            .collect::<Vec<_>>();
+        if !notes.is_empty() {
+            frame.push_str(&format!("  ({})", notes.join("; ")));
+        }
+        frame.push('\n');
+    }
+    frame
+}
+
+// Draws the given steps in the layout of the puzzle description, a header
+// for every motion followed by a frame per step. Step 0 is the initial state.
+pub fn render_steps(
+    rope: &Rope,
+    motions: &[Motion],
+    steps: RangeInclusive<usize>,
+    show_trail: bool,
+) -> Result<String, String> {
+    let trails = all_trails(rope)?;
+    let last_step = trails[0].len() - 1;
+    if steps.is_empty() || *steps.end() > last_step {
+        return Err(format!(
+            "Steps {}..{} are out of the run of {} steps!",
+            steps.start(),
+            steps.end(),
+            last_step
+        ));
+    }
+    let bounds = Bounds::of_steps(&trails, steps.clone());
+
+    // first step of every motion
+    let mut motion_starts = vec![];
+    let mut step = 1;
+    for motion in motions {
+        motion_starts.push((step, motion));
+        step += motion.steps as usize;
+    }
+
+    let mut output = String::new();
+    for step in steps.clone() {
+        if step == 0 {
+            output.push_str("== Initial State ==\n\n");
+        } else if let Some(&(first, motion)) =
+            motion_starts.iter().rev().find(|(first, _)| *first <= step)
+        {
+            if step == first || step == *steps.start() {
+                output.push_str(&format!("== {} ==\n\n", motion));
+            }
+        }
+        output.push_str(&render_frame(&trails, step, &bounds, show_trail));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+// Draws the initial state and the state after every motion, the way the
+// puzzle shows the larger example.
+pub fn render_motions(rope: &Rope, motions: &[Motion], show_trail: bool) -> Result<String, String> {
+    let trails = all_trails(rope)?;
+    let bounds = Bounds::of_steps(&trails, 0..=trails[0].len() - 1);
+    let mut output = String::from("== Initial State ==\n\n");
+    output.push_str(&render_frame(&trails, 0, &bounds, show_trail));
+    let mut step = 0;
+    for motion in motions {
+        step += motion.steps as usize;
+        output.push_str(&format!("\n== {} ==\n\n", motion));
+        output.push_str(&render_frame(&trails, step, &bounds, show_trail));
+    }
+    Ok(output)
+}
+
+// The cells one knot visited during the whole run, s marks the start. The
+// drawing spans the trails of all the tracked knots.
+pub fn render_visited(rope: &Rope, knot: usize) -> Result<String, String> {
+    let trail = rope
+        .trail(knot)
+        .ok_or_else(|| format!("Knot {} is not tracked!", knot))?;
+    let bounds = Bounds::of(rope.tracked().flat_map(|knot| rope.trail(knot).unwrap()));
+    let mut grid = vec![vec!['.'; bounds.width()]; bounds.height()];
+    for point in trail {
+        let (row, col) = bounds.cell(point).unwrap();
+        grid[row][col] = '#';
+    }
+    let (row, col) = bounds.cell(&Point::default()).unwrap();
+    grid[row][col] = 's';
+    Ok(grid
+        .iter()
+        .map(|cells| cells.iter().collect::<String>() + "\n")
+        .collect())
+}
@@ -0,0 +1,401 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hash;
+use std::str::FromStr;
+
+// A knot position, the rope only needs to know how a knot catches up with
+// the knot in front of it.
+pub trait Knot: Copy + Default + Eq + Hash + Display {
+    fn follow(self, leader: Self) -> Self;
+}
+
+// A move of the head by one step.
+pub trait Step: Copy + FromStr<Err = String> {
+    type Knot: Knot;
+
+    fn apply(self, knot: &mut Self::Knot);
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Knot for Point {
+    // A knot which is not touching its leader anymore moves one step towards
+    // it along every axis on which they differ. That covers any lag vector,
+    // also the diagonal ones of long ropes and diagonal head moves.
+    fn follow(self, leader: Self) -> Self {
+        let (dx, dy) = (leader.x - self.x, leader.y - self.y);
+        if dx.abs() <= 1 && dy.abs() <= 1 {
+            return self;
+        }
+        Self {
+            x: self.x + dx.signum(),
+            y: self.y + dy.signum(),
+        }
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.x, self.y)
+    }
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Point3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Knot for Point3 {
+    fn follow(self, leader: Self) -> Self {
+        let (dx, dy, dz) = (leader.x - self.x, leader.y - self.y, leader.z - self.z);
+        if dx.abs() <= 1 && dy.abs() <= 1 && dz.abs() <= 1 {
+            return self;
+        }
+        Self {
+            x: self.x + dx.signum(),
+            y: self.y + dy.signum(),
+            z: self.z + dz.signum(),
+        }
+    }
+}
+
+impl Display for Point3 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.x, self.y, self.z)
+    }
+}
+
+// Reads a direction made of one letter per axis at most, e.g. "R" or "UL".
+// `axes` gives the letters of the negative and positive way along each axis.
+fn parse_unit<const N: usize>(s: &str, axes: [(char, char); N]) -> Result<[i32; N], String> {
+    let mut unit = [0; N];
+    for letter in s.chars() {
+        let (axis, sign) = axes
+            .iter()
+            .enumerate()
+            .find_map(|(axis, &(negative, positive))| match letter {
+                _ if letter == negative => Some((axis, -1)),
+                _ if letter == positive => Some((axis, 1)),
+                _ => None,
+            })
+            .ok_or_else(|| format!("Unknown direction: {}!", s))?;
+        if unit[axis] != 0 {
+            return Err(format!("Unknown direction: {}!", s));
+        }
+        unit[axis] = sign;
+    }
+    if unit.iter().all(|&u| u == 0) {
+        return Err("Didn't get a direction!".to_owned());
+    }
+    Ok(unit)
+}
+
+// R, L, U, D and the diagonals UR, UL, DR, DL (in any letter order).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Direction {
+    pub dx: i32,
+    pub dy: i32,
+}
+
+impl Step for Direction {
+    type Knot = Point;
+
+    fn apply(self, knot: &mut Point) {
+        knot.x += self.dx;
+        knot.y += self.dy;
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let vertical = ["D", "", "U"][(self.dy + 1) as usize];
+        let horizontal = ["L", "", "R"][(self.dx + 1) as usize];
+        write!(f, "{}{}", vertical, horizontal)
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [dx, dy] = parse_unit(s, [('L', 'R'), ('D', 'U')])?;
+        Ok(Self { dx, dy })
+    }
+}
+
+// R/L along x, F/B along y and U/D along z, combined for the diagonals,
+// e.g. "RF" or "LBU".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Direction3 {
+    pub dx: i32,
+    pub dy: i32,
+    pub dz: i32,
+}
+
+impl Step for Direction3 {
+    type Knot = Point3;
+
+    fn apply(self, knot: &mut Point3) {
+        knot.x += self.dx;
+        knot.y += self.dy;
+        knot.z += self.dz;
+    }
+}
+
+impl Display for Direction3 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let x = ["L", "", "R"][(self.dx + 1) as usize];
+        let y = ["B", "", "F"][(self.dy + 1) as usize];
+        let z = ["D", "", "U"][(self.dz + 1) as usize];
+        write!(f, "{}{}{}", x, y, z)
+    }
+}
+
+impl FromStr for Direction3 {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [dx, dy, dz] = parse_unit(s, [('L', 'R'), ('B', 'F'), ('D', 'U')])?;
+        Ok(Self { dx, dy, dz })
+    }
+}
+
+// A line of the input, e.g. "R 4".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Motion<D = Direction> {
+    pub direction: D,
+    pub steps: u32,
+}
+
+impl<D: Display> Display for Motion<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.direction, self.steps)
+    }
+}
+
+impl<D: Step> FromStr for Motion<D> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.split_whitespace();
+        let direction = split
+            .next()
+            .ok_or_else(|| "Didn't get a direction!".to_owned())?
+            .parse()?;
+        let steps = split
+            .next()
+            .ok_or_else(|| "Didn't get number of steps!".to_owned())?
+            .parse()
+            .map_err(|_| "Couldn't parse number of steps!".to_owned())?;
+        if split.next().is_some() {
+            return Err(format!("Unexpected text after the motion: {}", s));
+        }
+        Ok(Self { direction, steps })
+    }
+}
+
+// Parses the motions skipping empty lines, errors point at the offending line.
+pub fn read_motions<'a, D: Step>(
+    lines: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<Motion<D>>, String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            Motion::from_str(line).map_err(|err| format!("Line {}: {}", idx + 1, err))
+        })
+        .collect()
+}
+
+// Knot 0 is the head and the last knot is the tail. Each tracked knot gets
+// its whole trail, the start position followed by one position per step.
+pub struct Rope<P = Point> {
+    knots: Vec<P>,
+    trails: Vec<Option<Vec<P>>>,
+}
+
+impl<P: Knot> Rope<P> {
+    // A rope tracking only its tail.
+    pub fn new(num_of_knots: usize) -> Result<Self, String> {
+        Self::with_tracked(num_of_knots, &[num_of_knots.saturating_sub(1)])
+    }
+
+    pub fn with_tracked(num_of_knots: usize, tracked: &[usize]) -> Result<Self, String> {
+        if num_of_knots == 0 {
+            return Err("A rope needs at least one knot!".to_owned());
+        }
+        let mut trails = vec![None; num_of_knots];
+        for &knot in tracked {
+            let trail = trails.get_mut(knot).ok_or_else(|| {
+                format!("There is no knot {} in a rope of {}!", knot, num_of_knots)
+            })?;
+            *trail = Some(vec![P::default()]);
+        }
+        Ok(Self {
+            knots: vec![P::default(); num_of_knots],
+            trails,
+        })
+    }
+
+    pub fn knots(&self) -> &[P] {
+        &self.knots
+    }
+
+    pub fn tail(&self) -> usize {
+        self.knots.len() - 1
+    }
+
+    pub fn tracked(&self) -> impl Iterator<Item = usize> + '_ {
+        self.trails
+            .iter()
+            .enumerate()
+            .filter_map(|(knot, trail)| trail.as_ref().map(|_| knot))
+    }
+
+    // Moves the head by one step and lets the rest of the knots follow.
+    pub fn step<D: Step<Knot = P>>(&mut self, direction: D) {
+        direction.apply(&mut self.knots[0]);
+        for idx in 1..self.knots.len() {
+            self.knots[idx] = self.knots[idx].follow(self.knots[idx - 1]);
+        }
+        for (knot, trail) in self.knots.iter().zip(self.trails.iter_mut()) {
+            if let Some(trail) = trail {
+                trail.push(*knot);
+            }
+        }
+    }
+
+    pub fn apply<D: Step<Knot = P>>(&mut self, motion: &Motion<D>) {
+        for _ in 0..motion.steps {
+            self.step(motion.direction);
+        }
+    }
+
+    // None for the knots which are not tracked.
+    pub fn trail(&self, knot: usize) -> Option<&[P]> {
+        self.trails.get(knot)?.as_deref()
+    }
+
+    pub fn visited(&self, knot: usize) -> Option<HashSet<P>> {
+        self.trail(knot)
+            .map(|trail| trail.iter().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+    const LARGE_EXAMPLE: &str = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+
+    fn tail_cells(knots: usize, motions: &str) -> usize {
+        let mut rope = Rope::new(knots).unwrap();
+        for motion in read_motions::<Direction>(motions.lines()).unwrap() {
+            rope.apply(&motion);
+        }
+        rope.visited(rope.tail()).unwrap().len()
+    }
+
+    // The rules of the first version, which knew about orthogonal head moves
+    // only and the lags they leave behind.
+    fn move_next_to(knot: Point, leader: Point) -> Point {
+        let (dx, dy) = (leader.x - knot.x, leader.y - knot.y);
+        if dx.abs() <= 1 && dy.abs() <= 1 {
+            return knot;
+        }
+        let step = match (dx, dy) {
+            (2, 0) => (1, 0),
+            (-2, 0) => (-1, 0),
+            (0, 2) => (0, 1),
+            (0, -2) => (0, -1),
+            _ if dx >= 1 && dy >= 1 => (1, 1),
+            _ if dx <= -1 && dy >= 1 => (-1, 1),
+            _ if dx >= 1 && dy <= -1 => (1, -1),
+            _ => (-1, -1),
+        };
+        Point {
+            x: knot.x + step.0,
+            y: knot.y + step.1,
+        }
+    }
+
+    #[test]
+    fn readme_examples() {
+        assert_eq!(tail_cells(2, SMALL_EXAMPLE), 13);
+        assert_eq!(tail_cells(10, SMALL_EXAMPLE), 1);
+        assert_eq!(tail_cells(10, LARGE_EXAMPLE), 36);
+    }
+
+    #[test]
+    fn follows_like_the_first_version() {
+        let knot = Point::default();
+        for x in -2..=2 {
+            for y in -2..=2 {
+                let leader = Point { x, y };
+                assert_eq!(
+                    knot.follow(leader),
+                    move_next_to(knot, leader),
+                    "{}",
+                    leader
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parses_diagonals() {
+        assert_eq!("UR".parse(), Ok(Direction { dx: 1, dy: 1 }));
+        assert_eq!("RU".parse(), Ok(Direction { dx: 1, dy: 1 }));
+        assert_eq!("DL".parse(), Ok(Direction { dx: -1, dy: -1 }));
+        assert_eq!(Direction { dx: -1, dy: -1 }.to_string(), "DL");
+        assert!("UD".parse::<Direction>().is_err());
+        assert!("RR".parse::<Direction>().is_err());
+        assert_eq!(
+            "UR 3".parse(),
+            Ok(Motion {
+                direction: Direction { dx: 1, dy: 1 },
+                steps: 3
+            })
+        );
+    }
+
+    #[test]
+    fn flat_3d_rope_moves_like_the_2d_one() {
+        let motions = read_motions::<Direction>(LARGE_EXAMPLE.lines()).unwrap();
+        let mut flat = Rope::new(10).unwrap();
+        let mut rope = Rope::<Point3>::new(10).unwrap();
+        for motion in motions {
+            flat.apply(&motion);
+            // y of the 2D rope is F/B of the 3D one
+            rope.apply(&Motion {
+                direction: Direction3 {
+                    dx: motion.direction.dx,
+                    dy: motion.direction.dy,
+                    dz: 0,
+                },
+                steps: motion.steps,
+            });
+        }
+        let projected = rope
+            .trail(rope.tail())
+            .unwrap()
+            .iter()
+            .map(|knot| {
+                assert_eq!(knot.z, 0);
+                Point {
+                    x: knot.x,
+                    y: knot.y,
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(projected, flat.trail(flat.tail()).unwrap());
+    }
+}